@@ -1,5 +1,5 @@
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
-use strands::two_words_no_diag_overlap;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use strands::{square_corner_mask, two_words_no_diag_overlap, two_words_no_diag_overlap_shifted};
 
 // Function to generate a mock block and board configuration.
 // These are just placeholders; you may want to use actual configurations relevant to your use case.
@@ -11,6 +11,17 @@ fn generate_mock_data(board_width: usize, board_height: usize) -> (usize, usize)
     (block, board)
 }
 
+// The block fills every even row and the board every odd row, so the two never
+// cross and every 2x2 square has to be checked.
+fn generate_striped_data(board_width: usize, board_height: usize) -> (usize, usize) {
+    let row = (1 << board_width) - 1;
+    let block = (0..board_height)
+        .step_by(2)
+        .fold(0, |acc, y| acc | (row << (y * board_width)));
+    let board = block << board_width;
+    (block, board)
+}
+
 fn bench_no_diagonal_overlap(c: &mut Criterion) {
     let mut group = c.benchmark_group("no_diagonal_overlap");
 
@@ -30,6 +41,35 @@ fn bench_no_diagonal_overlap(c: &mut Criterion) {
                 });
             },
         );
+
+        // Both versions on words that never cross, so neither can stop early
+        let (block, board) = generate_striped_data(width, height);
+        group.bench_with_input(
+            BenchmarkId::new("Loop Stripes", format!("{}x{}", width, height)),
+            &(block, board),
+            |b, &(block, board)| {
+                b.iter(|| {
+                    two_words_no_diag_overlap(black_box(block), black_box(board), width, height)
+                });
+            },
+        );
+
+        // The shifted version only needs the corner mask worked out once per board
+        let corner_mask = square_corner_mask(width, height);
+        group.bench_with_input(
+            BenchmarkId::new("Shifted Stripes", format!("{}x{}", width, height)),
+            &(block, board),
+            |b, &(block, board)| {
+                b.iter(|| {
+                    two_words_no_diag_overlap_shifted(
+                        black_box(block),
+                        black_box(board),
+                        width,
+                        corner_mask,
+                    )
+                });
+            },
+        );
     }
 
     group.finish();
@@ -0,0 +1,401 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Board {
+    pub letters: Vec<char>,
+    pub w: usize,
+    pub h: usize,
+}
+
+impl Board {
+    /// Goes from groups of six letters separated by a space, to a flat array
+    pub fn parse_flat_board(letters: &str, width: usize, height: usize) -> Board {
+        let mut bletters = Vec::with_capacity(width * height);
+
+        for c in letters.replace(' ', "").chars() {
+            bletters.push(c);
+        }
+
+        Board {
+            letters: bletters,
+            w: width,
+            h: height,
+        }
+    }
+
+    /// Goes from rows of letters separated by a space, to a flat array, taking the
+    /// size of the board from the rows
+    pub fn parse_rows(letters: &str) -> Board {
+        let rows: Vec<&str> = letters.split_whitespace().collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        Board::parse_flat_board(letters, width, rows.len())
+    }
+
+    /// Return a list of neighbors. Works as like a 2d array of width `w` and height `h`.
+    /// Gets diagonal neighbors too.
+    ///
+    /// Copied from
+    /// <https://stackoverflow.com/questions/9355537/finding-neighbors-of-2d-array-when-represented-as-1d-array>
+    ///
+    /// Could perhaps write a version of this that takes in a mutable bit array, sets
+    /// everything to zero, then sets the right ones to true.
+    pub fn get_neighbors(&self, i: usize) -> Vec<usize> {
+        let size = self.w * self.h;
+        let mut neighbors: Vec<usize> = Vec::new();
+
+        if i.checked_sub(self.w).is_some() {
+            neighbors.push(i - self.w); // north
+        }
+
+        if !i.is_multiple_of(self.w) {
+            neighbors.push(i - 1); // west
+        }
+
+        if !(i + 1).is_multiple_of(self.w) {
+            neighbors.push(i + 1); // east
+        }
+
+        if (i + self.w) < size {
+            neighbors.push(i + self.w); // south
+        }
+
+        if (i.checked_sub(self.w + 1).is_some()) & (!i.is_multiple_of(self.w)) {
+            neighbors.push(i - self.w - 1); // northwest
+        }
+
+        if ((i + 1).checked_sub(self.w).is_some()) & (!(i + 1).is_multiple_of(self.w)) {
+            neighbors.push(i + 1 - self.w); // northeast
+        }
+
+        if ((i + self.w - 1) < size) & (!i.is_multiple_of(self.w)) {
+            neighbors.push(i + self.w - 1); // southwest
+        }
+
+        if ((i + self.w + 1) < size) & (!(i + 1).is_multiple_of(self.w)) {
+            neighbors.push(i + self.w + 1); // southeast
+        }
+
+        neighbors
+    }
+
+    /// The words that can be formed from each starting point on the board, indexed
+    /// by starting point. Each starting point is searched on its own thread.
+    pub fn find_words_that_fit(&self, words: &[&str]) -> Vec<Vec<(String, Vec<usize>)>> {
+        (0..self.letters.len())
+            .into_par_iter()
+            .map(|start_point| self.find_valid_words_from_start(start_point, words))
+            .collect()
+    }
+
+    /// From a given starting point on the board, what words can be formed?
+    pub fn find_valid_words_from_start(
+        &self,
+        start_point: usize,
+        words: &[&str],
+    ) -> Vec<(String, Vec<usize>)> {
+        let mut result: Vec<(String, Vec<usize>)> = Vec::new();
+
+        let start_spot = vec![start_point];
+        let new_words: Vec<&str> = words
+            .iter()
+            .filter(|w| w.starts_with(self.letters[start_point]))
+            .copied()
+            .collect();
+
+        result.extend(self.find_next(&new_words, &start_spot, start_point));
+        result
+    }
+
+    /// From a given starting point, what paths spell two or more words run together,
+    /// like "dancemoves"? Each result is the words and the path spelling them. At
+    /// most `max_parts` words are joined, and `words` must be sorted.
+    pub fn find_compounds_from_start(
+        &self,
+        start_point: usize,
+        words: &[&str],
+        max_parts: usize,
+    ) -> Vec<(Vec<String>, Vec<usize>)> {
+        let mut result = Vec::new();
+        let mut path = vec![start_point];
+        let candidates = with_prefix(words, &self.letters[start_point].to_string());
+        let mut parts = Vec::new();
+        self.find_next_compound(
+            words,
+            candidates,
+            &mut path,
+            &mut parts,
+            max_parts,
+            &mut result,
+        );
+        result
+    }
+
+    /// A recursive method for finding compounds. `parts` are the words already
+    /// finished along `path`, and `candidates` are the words the rest of it could
+    /// still become.
+    fn find_next_compound(
+        &self,
+        words: &[&str],
+        candidates: &[&str],
+        path: &mut Vec<usize>,
+        parts: &mut Vec<String>,
+        max_parts: usize,
+        result: &mut Vec<(Vec<String>, Vec<usize>)>,
+    ) {
+        let part_start = parts.iter().map(|part| part.chars().count()).sum();
+        let part: String = path[part_start..]
+            .iter()
+            .map(|&idx| self.letters[idx])
+            .collect();
+        let current_position = *path.last().unwrap();
+
+        // The shortest candidate comes first, so a finished word is always there
+        if candidates.first() == Some(&part.as_str()) {
+            parts.push(part.clone());
+            if parts.len() >= 2 {
+                result.push((parts.clone(), path.clone()));
+            }
+            // Start the next word on any free neighbor
+            if parts.len() < max_parts {
+                for nbr_idx in self.get_neighbors(current_position) {
+                    if path.contains(&nbr_idx) {
+                        continue;
+                    }
+                    let next = with_prefix(words, &self.letters[nbr_idx].to_string());
+                    if next.is_empty() {
+                        continue;
+                    }
+                    path.push(nbr_idx);
+                    self.find_next_compound(words, next, path, parts, max_parts, result);
+                    path.pop();
+                }
+            }
+            parts.pop();
+        }
+
+        // Carry on spelling the current word
+        for nbr_idx in self.get_neighbors(current_position) {
+            if path.contains(&nbr_idx) {
+                continue;
+            }
+            let mut longer = part.clone();
+            longer.push(self.letters[nbr_idx]);
+            let rest = with_prefix(candidates, &longer);
+            if rest.is_empty() {
+                continue;
+            }
+            path.push(nbr_idx);
+            self.find_next_compound(words, rest, path, parts, max_parts, result);
+            path.pop();
+        }
+    }
+
+    /// A recursive method for finding valid words
+    fn find_next(
+        &self,
+        words: &[&str],
+        start_spots: &[usize],
+        current_board_position: usize,
+    ) -> Vec<(String, Vec<usize>)> {
+        // If no more words, end
+        if words.is_empty() {
+            return vec![];
+        }
+
+        // Otherwise, loop over the neighbors, and return the results
+        let mut result = Vec::new();
+        let nbr_inds = self.get_neighbors(current_board_position);
+        for nbr_idx in nbr_inds {
+            // If this letter is already seen in the `start_spots`, continue
+            if start_spots.contains(&nbr_idx) {
+                continue;
+            }
+            // What word is created by adding this neighbor?
+            let word = self.make_word_from_inds(start_spots, nbr_idx);
+
+            // If adding this neighbor makes a complete word, push to result
+            if words.contains(&word.as_str()) {
+                let mut positions = start_spots.to_vec();
+                positions.push(nbr_idx);
+                result.push((word.clone(), positions));
+            }
+
+            // What words are left for this word?
+            let rem_words: Vec<&str> = words
+                .iter()
+                .filter(|w| w.starts_with(&word))
+                .copied()
+                .collect();
+
+            // Quit if none left
+            if rem_words.is_empty() {
+                continue;
+            }
+
+            // Call again from this neighbor position and push to the the result
+            let mut new_spots: Vec<usize> = start_spots.to_vec();
+            new_spots.push(nbr_idx);
+            result.extend(self.find_next(&rem_words, &new_spots, nbr_idx));
+        }
+        result
+    }
+
+    /// The spot with a name like "r2c1", for row 2 and column 1, counting from zero
+    pub fn parse_cell(&self, name: &str) -> Option<usize> {
+        let (row, col) = name.trim().strip_prefix('r')?.split_once('c')?;
+        let (row, col): (usize, usize) = (row.parse().ok()?, col.parse().ok()?);
+        (row < self.h && col < self.w).then_some(row * self.w + col)
+    }
+
+    /// The name of a spot, like "r2c1", as read by [`Board::parse_cell`]
+    pub fn cell_name(&self, idx: usize) -> String {
+        format!("r{}c{}", idx / self.w, idx % self.w)
+    }
+
+    /// Draw the board with the letters on `path` in capitals and every other spot as
+    /// a dot, one line per row
+    pub fn draw_path(&self, path: &[usize]) -> String {
+        (0..self.h)
+            .map(|y| {
+                (y * self.w..(y + 1) * self.w)
+                    .map(|idx| {
+                        if path.contains(&idx) {
+                            self.letters[idx].to_ascii_uppercase()
+                        } else {
+                            '.'
+                        }
+                    })
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Given a set of indices, create a new word from the letters at those indices
+    fn make_word_from_inds(&self, inds_so_far: &[usize], new_ind: usize) -> String {
+        let mut word: String = inds_so_far.iter().map(|idx| self.letters[*idx]).collect();
+        word.push(self.letters[new_ind]);
+        word
+    }
+}
+
+/// The run of sorted `words` that start with `prefix`
+fn with_prefix<'a, 'b>(words: &'a [&'b str], prefix: &str) -> &'a [&'b str] {
+    let start = words.partition_point(|w| *w < prefix);
+    let len = words[start..].partition_point(|w| w.starts_with(prefix));
+    &words[start..start + len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::fs;
+
+    #[test]
+    fn test_parse_flat_board() {
+        let letters = "olwish heucbl sykoda ecpeny sheyub ranngm ormora hscksh";
+        let want_letters: Vec<char> = letters.replace(' ', "").chars().collect();
+        let want = Board {
+            letters: want_letters,
+            w: 6,
+            h: 8,
+        };
+        let got = Board::parse_flat_board(letters, 6, 8);
+
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn test_parse_rows() {
+        let board = Board::parse_rows(" tal rgo esn ");
+        assert_eq!(Board::parse_flat_board("tal rgo esn", 3, 3), board);
+    }
+
+    #[rstest]
+    #[case(0, vec![1, 3, 4])]
+    #[case(1, vec![0, 2, 4, 3, 5])]
+    #[case(2, vec![1, 5, 4])]
+    #[case(3, vec![0, 4, 6, 1, 7])]
+    #[case(4, vec![1, 3, 5, 7, 0, 2, 6, 8])]
+    #[case(5, vec![2, 4, 8, 1, 7])]
+    #[case(6, vec![3, 7, 4])]
+    #[case(7, vec![4, 6, 8, 3, 5])]
+    #[case(8, vec![5, 7, 4])]
+    fn test_get_neighbors(#[case] idx: usize, #[case] want: Vec<usize>) {
+        let board = Board::parse_flat_board("abc def ghi", 3, 3);
+        let got = board.get_neighbors(idx);
+        assert_eq!(want, got);
+    }
+
+    #[rstest]
+    #[case("r0c0", Some(0))]
+    #[case("r2c1", Some(7))]
+    #[case(" r1c2 ", Some(5))]
+    #[case("r3c0", None)] // off the bottom
+    #[case("r0c3", None)] // off the side
+    #[case("c1r2", None)]
+    #[case("r1", None)]
+    fn test_parse_cell(#[case] name: &str, #[case] want: Option<usize>) {
+        let board = Board::parse_flat_board("abc def ghi", 3, 3);
+        assert_eq!(want, board.parse_cell(name));
+        if let Some(idx) = want {
+            assert_eq!(name.trim(), board.cell_name(idx));
+        }
+    }
+
+    #[test]
+    fn test_draw_path() {
+        let board = Board::parse_flat_board("tal rgo esn", 3, 3);
+        assert_eq!("T A L\n. . O\n. . N", board.draw_path(&[0, 1, 2, 5, 8]));
+    }
+
+    #[rstest]
+    #[case(0, vec![("talon".to_string(), vec![0, 1, 2, 5, 8])])]
+    #[case(1, vec![("argon".to_string(), vec![1, 3, 4, 5, 8])])]
+    #[case(2, vec![("long".to_string(), vec![2, 5, 8, 4]), ("lose".to_string(), vec![2, 5, 7,6])])]
+    #[case(3, vec![("rage".to_string(), vec![3, 1, 4, 6])])]
+    #[case(4, vec![("glare".to_string(), vec![4, 2, 1, 3, 6])])]
+    #[case(5, vec![("ogre".to_string(), vec![5, 4, 3, 6])])]
+    #[case(6, vec![("ergo".to_string(), vec![6, 3, 4, 5])])]
+    #[case(7, vec![("solar".to_string(), vec![7, 5, 2, 1, 3])])]
+    #[case(8, vec![("nose".to_string(), vec![8, 5, 7, 6])])]
+    fn test_find_valid_words_from_start(
+        #[case] start_point: usize,
+        #[case] want: Vec<(String, Vec<usize>)>,
+    ) {
+        let board = Board::parse_flat_board("tal rgo esn", 3, 3);
+
+        let words = vec![
+            "talon", "ogre", "sunny", "batch", "solar", "argon", "ergo", "lose", "long", "rage",
+            "tart", "nose", "glare",
+        ];
+
+        let mut got = board.find_valid_words_from_start(start_point, &words);
+        got.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn test_find_valid_words_in_parallel() {
+        let board = Board::parse_flat_board(
+            "hgueds uovaos lsnltw etcfme eoreor tuivkm tpekoo eslawn",
+            6,
+            8,
+        );
+        let words =
+            fs::read_to_string("american_english_dictionary.txt").expect("Unable to read file");
+        let valid_words: Vec<&str> = words.lines().filter(|&w| w.len() >= 4).collect();
+
+        let want: Vec<Vec<(String, Vec<usize>)>> = (0..(6 * 8))
+            .map(|start_point| board.find_valid_words_from_start(start_point, &valid_words))
+            .collect();
+        let got = board.find_words_that_fit(&valid_words);
+
+        assert_eq!(want, got);
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::is_spangram;
+use crate::words::{parse_phrases, squash};
+
+/// A list of words on one theme, like animals or dances
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Category {
+    pub name: String,
+    /// The words with their letters run together, as on the board
+    pub words: HashSet<String>,
+}
+
+impl Category {
+    /// Read a category word list, one word or phrase per line. Blank lines and
+    /// lines starting with `#` are skipped.
+    pub fn parse(name: &str, text: &str) -> Self {
+        Category {
+            name: name.to_string(),
+            words: parse_phrases(text)
+                .iter()
+                .map(|entry| squash(entry))
+                .collect(),
+        }
+    }
+
+    /// The candidates from [`crate::Board::find_valid_words_from_start`] that are in
+    /// this category, sorted
+    pub fn words_on_board(&self, words_that_fit: &[Vec<(String, Vec<usize>)>]) -> Vec<String> {
+        let on_board: BTreeSet<&String> = words_that_fit
+            .iter()
+            .flatten()
+            .map(|(word, _)| word)
+            .filter(|word| self.words.contains(*word))
+            .collect();
+        on_board.into_iter().cloned().collect()
+    }
+
+    /// Remove the placements of words outside this category, except those that
+    /// could be the spangram, which rarely belongs to the category itself. Returns
+    /// how many placements were removed.
+    pub fn restrict(
+        &self,
+        words_that_fit: &mut [Vec<(String, Vec<usize>)>],
+        board_w: usize,
+        board_h: usize,
+    ) -> usize {
+        let mut removed = 0;
+        for start_point in words_that_fit.iter_mut() {
+            let before = start_point.len();
+            start_point.retain(|(word, path)| {
+                let block = path.iter().fold(0, |acc, &idx| acc | (1 << idx));
+                self.words.contains(word) || is_spangram(block, board_w, board_h)
+            });
+            removed += before - start_point.len();
+        }
+        removed
+    }
+}
+
+/// Order categories by how many of their words are on the board, most first, along
+/// with those words. Categories with no words on the board are left out.
+pub fn rank_categories<'a>(
+    categories: &'a [Category],
+    words_that_fit: &[Vec<(String, Vec<usize>)>],
+) -> Vec<(&'a Category, Vec<String>)> {
+    let mut ranked: Vec<(&Category, Vec<String>)> = categories
+        .iter()
+        .map(|category| (category, category.words_on_board(words_that_fit)))
+        .filter(|(_, words)| !words.is_empty())
+        .collect();
+    ranked.sort_by(|(a, a_words), (b, b_words)| {
+        b_words.len().cmp(&a_words.len()).then(a.name.cmp(&b.name))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // On a 3x3 board
+    fn words_that_fit() -> Vec<Vec<(String, Vec<usize>)>> {
+        vec![
+            vec![
+                ("cat".to_string(), vec![0, 1, 2]),
+                ("cow".to_string(), vec![0, 3]),
+            ],
+            vec![("owl".to_string(), vec![4, 5])],
+            vec![
+                ("tango".to_string(), vec![6, 7]),
+                ("cat".to_string(), vec![2, 1, 0]),
+            ],
+        ]
+    }
+
+    #[test]
+    fn test_parse() {
+        let category = Category::parse("animals", "# Farm\nCow\n\nguinea pig\n");
+        let want = HashSet::from(["cow".to_string(), "guineapig".to_string()]);
+        assert_eq!(want, category.words);
+    }
+
+    #[test]
+    fn test_restrict() {
+        let category = Category::parse("animals", "cow\nowl\n");
+        let mut words_that_fit = words_that_fit();
+        // "cat" reaches across, so it could still be the spangram
+        assert_eq!(1, category.restrict(&mut words_that_fit, 3, 3));
+        let left: Vec<&str> = words_that_fit
+            .iter()
+            .flatten()
+            .map(|(word, _)| word.as_str())
+            .collect();
+        assert_eq!(vec!["cat", "cow", "owl", "cat"], left);
+    }
+
+    #[test]
+    fn test_rank_categories() {
+        let categories = vec![
+            Category::parse("dances", "tango\nwaltz\n"),
+            Category::parse("animals", "cat\ncow\nowl\nyak\n"),
+            Category::parse("colors", "red\n"),
+        ];
+        let ranked = rank_categories(&categories, &words_that_fit());
+        let got: Vec<(&str, usize)> = ranked
+            .iter()
+            .map(|(category, words)| (category.name.as_str(), words.len()))
+            .collect();
+        assert_eq!(vec![("animals", 3), ("dances", 1)], got);
+        assert_eq!(vec!["cat", "cow", "owl"], ranked[0].1);
+    }
+}
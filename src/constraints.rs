@@ -0,0 +1,106 @@
+/// Remove every placement of `words`, and every placement along one of `paths`, from
+/// the candidates found by [`crate::Board::find_valid_words_from_start`]. Returns
+/// how many placements were removed.
+pub fn exclude_placements(
+    words_that_fit: &mut [Vec<(String, Vec<usize>)>],
+    words: &[String],
+    paths: &[Vec<usize>],
+) -> usize {
+    let mut removed = 0;
+    for start_point in words_that_fit.iter_mut() {
+        let before = start_point.len();
+        start_point.retain(|(word, path)| !words.contains(word) && !paths.contains(path));
+        removed += before - start_point.len();
+    }
+    removed
+}
+
+/// Make exactly one placement cover precisely `cells`, as when the game's hint
+/// highlights a word without saying which order to read it in. Placements that
+/// cover only some of the cells, or spill outside them, are removed. Returns how
+/// many placements are left covering the cells.
+pub fn apply_hint(words_that_fit: &mut [Vec<(String, Vec<usize>)>], cells: &[usize]) -> usize {
+    let hint = cells.iter().fold(0usize, |acc, &idx| acc | (1 << idx));
+    let mut matching = 0;
+    for start_point in words_that_fit.iter_mut() {
+        start_point.retain(|(_, path)| {
+            let block = path.iter().fold(0usize, |acc, &idx| acc | (1 << idx));
+            if block == hint {
+                matching += 1;
+            }
+            block == hint || block & hint == 0
+        });
+    }
+    matching
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(&[], &[], 0, &["talon", "long", "lose", "rage", "regs"])]
+    #[case(&["long"], &[], 1, &["talon", "lose", "rage", "regs"])]
+    #[case(&[], &[vec![3, 6, 4, 7]], 1, &["talon", "long", "lose", "rage"])]
+    #[case(&[], &[vec![7, 4, 6, 3]], 0, &["talon", "long", "lose", "rage", "regs"])] // backwards
+    #[case(&["talon", "rage"], &[vec![2, 5, 7, 6]], 3, &["long", "regs"])]
+    fn test_exclude_placements(
+        #[case] words: &[&str],
+        #[case] paths: &[Vec<usize>],
+        #[case] want_removed: usize,
+        #[case] want: &[&str],
+    ) {
+        let mut words_that_fit: Vec<Vec<(String, Vec<usize>)>> = vec![
+            vec![("talon".to_string(), vec![0, 1, 2, 5, 8])],
+            vec![
+                ("long".to_string(), vec![2, 5, 8, 4]),
+                ("lose".to_string(), vec![2, 5, 7, 6]),
+            ],
+            vec![
+                ("rage".to_string(), vec![3, 1, 4, 6]),
+                ("regs".to_string(), vec![3, 6, 4, 7]),
+            ],
+        ];
+        let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+
+        let removed = exclude_placements(&mut words_that_fit, &words, paths);
+        assert_eq!(want_removed, removed);
+        let left: Vec<&str> = words_that_fit
+            .iter()
+            .flatten()
+            .map(|(word, _)| word.as_str())
+            .collect();
+        assert_eq!(want, left);
+    }
+
+    #[rstest]
+    #[case(&[3, 4, 6, 7], 1, &["talon", "regs"])] // one way to fill the square
+    #[case(&[2, 5, 8], 0, &["rage", "regs"])] // nothing fits exactly
+    #[case(&[0, 1, 2, 5, 8], 1, &["talon", "regs"])]
+    fn test_apply_hint(
+        #[case] cells: &[usize],
+        #[case] want_matching: usize,
+        #[case] want: &[&str],
+    ) {
+        let mut words_that_fit: Vec<Vec<(String, Vec<usize>)>> = vec![
+            vec![("talon".to_string(), vec![0, 1, 2, 5, 8])],
+            vec![
+                ("long".to_string(), vec![2, 5, 8, 4]),
+                ("lose".to_string(), vec![2, 5, 7, 6]),
+            ],
+            vec![
+                ("rage".to_string(), vec![3, 1, 4, 6]),
+                ("regs".to_string(), vec![3, 6, 4, 7]),
+            ],
+        ];
+
+        assert_eq!(want_matching, apply_hint(&mut words_that_fit, cells));
+        let left: Vec<&str> = words_that_fit
+            .iter()
+            .flatten()
+            .map(|(word, _)| word.as_str())
+            .collect();
+        assert_eq!(want, left);
+    }
+}
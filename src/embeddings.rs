@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+/// Word vectors read from a GloVe or word2vec text file, scaled to unit length so
+/// their dot product is the cosine similarity
+#[derive(Debug, Clone, Default)]
+pub struct Embeddings {
+    vectors: HashMap<String, Vec<f32>>,
+}
+
+impl Embeddings {
+    /// Read one word per line followed by its vector, e.g. "house 0.12 -0.4 ...".
+    /// The header line of a word2vec file, and lines that don't match the first
+    /// vector's length, are skipped. Only words for which `keep` returns true are
+    /// stored, since full files hold far more words than any board needs.
+    pub fn parse(text: &str, keep: impl Fn(&str) -> bool) -> Self {
+        Self::read(text.as_bytes(), keep).expect("reading from memory can't fail")
+    }
+
+    /// Like [`Embeddings::parse`], but a line at a time, so only the kept words
+    /// are ever held in memory
+    pub fn read(reader: impl BufRead, keep: impl Fn(&str) -> bool) -> io::Result<Self> {
+        let mut vectors = HashMap::new();
+        let mut dims = None;
+        for line in reader.lines() {
+            let line = line?;
+            let mut fields = line.split_whitespace();
+            let Some(word) = fields.next() else {
+                continue;
+            };
+            let word = word.to_lowercase();
+            // Once the vector length is known, words that aren't kept needn't be read
+            if dims.is_some() && !keep(&word) {
+                continue;
+            }
+            let Ok(vector) = fields.map(str::parse).collect::<Result<Vec<f32>, _>>() else {
+                continue;
+            };
+            // The word2vec header is just the word count and the vector length
+            if vector.len() < 2 || *dims.get_or_insert(vector.len()) != vector.len() {
+                continue;
+            }
+            if keep(&word) {
+                vectors.insert(word, unit(vector));
+            }
+        }
+        Ok(Embeddings { vectors })
+    }
+
+    /// The vector for a word, if it has one
+    pub fn get(&self, word: &str) -> Option<&[f32]> {
+        self.vectors.get(word).map(Vec::as_slice)
+    }
+
+    /// The average direction of the words in some text, like a theme clue. Words
+    /// without a vector are ignored, and `None` is returned if none have one.
+    pub fn text_vector(&self, text: &str) -> Option<Vec<f32>> {
+        let mut sum: Option<Vec<f32>> = None;
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let Some(vector) = self.get(&word.to_lowercase()) else {
+                continue;
+            };
+            match &mut sum {
+                Some(sum) => sum.iter_mut().zip(vector).for_each(|(s, v)| *s += v),
+                None => sum = Some(vector.to_vec()),
+            }
+        }
+        sum.map(unit)
+    }
+
+    /// How close a word is to `target`, from -1 to 1. Words without a vector score
+    /// zero, as if unrelated.
+    pub fn similarity(&self, word: &str, target: &[f32]) -> f32 {
+        self.get(word).map_or(0.0, |vector| dot(vector, target))
+    }
+
+    /// How well a set of words hangs together: the average of their similarity to
+    /// the clue and their average similarity to each other. Words without a vector
+    /// count as unrelated to everything, so obscure fillers drag a cover down.
+    pub fn coherence(&self, words: &[String], clue: &[f32]) -> f32 {
+        if words.is_empty() {
+            return 0.0;
+        }
+        let to_clue = words
+            .iter()
+            .map(|word| self.similarity(word, clue))
+            .sum::<f32>()
+            / words.len() as f32;
+        let mut to_each_other = 0.0;
+        let mut pairs = 0;
+        for (i, a) in words.iter().enumerate() {
+            for b in &words[i + 1..] {
+                if let (Some(a), Some(b)) = (self.get(a), self.get(b)) {
+                    to_each_other += dot(a, b);
+                }
+                pairs += 1;
+            }
+        }
+        if pairs > 0 {
+            (to_clue + to_each_other / pairs as f32) / 2.0
+        } else {
+            to_clue
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Scale a vector to length one, leaving all zeros alone
+fn unit(mut vector: Vec<f32>) -> Vec<f32> {
+    let length = dot(&vector, &vector).sqrt();
+    if length > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= length);
+    }
+    vector
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const VECTORS: &str = "5 2\n\
+        dance 1 0\n\
+        ballet 0.9 0.1\n\
+        tango 0.8 0.2\n\
+        toast 0 1\n\
+        Moves 1 0.1\n\
+        broken 1\n";
+
+    #[test]
+    fn test_parse() {
+        let embeddings = Embeddings::parse(VECTORS, |word| word != "toast");
+        for word in ["dance", "ballet", "tango", "moves"] {
+            assert!(embeddings.get(word).is_some());
+        }
+        assert!(embeddings.get("toast").is_none());
+        assert!(embeddings.get("broken").is_none());
+        let moves = embeddings.get("moves").unwrap();
+        assert!((dot(moves, moves) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_read_error() {
+        let text: &[u8] = b"dance 1 0\n\xff 1 0\n";
+        assert!(Embeddings::read(text, |_| true).is_err());
+    }
+
+    #[rstest]
+    #[case("Dance moves!", true)]
+    #[case("unknown words", false)]
+    fn test_text_vector(#[case] clue: &str, #[case] found: bool) {
+        let embeddings = Embeddings::parse(VECTORS, |_| true);
+        assert_eq!(found, embeddings.text_vector(clue).is_some());
+    }
+
+    #[test]
+    fn test_coherence() {
+        let embeddings = Embeddings::parse(VECTORS, |_| true);
+        let clue = embeddings.text_vector("dance").unwrap();
+        let coherence = |words: &[&str]| {
+            let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+            embeddings.coherence(&words, &clue)
+        };
+        assert!(coherence(&["ballet", "tango"]) > coherence(&["toast", "dance"]));
+        assert!(coherence(&["toast", "dance"]) > coherence(&["ballet", "zzzz"]));
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::words::squash;
+use crate::{Answer, Solution};
+
+/// How a solution's words compare to a puzzle's official answers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Accuracy {
+    /// How many words the solution has
+    pub found: usize,
+    /// How many of them are answers
+    pub correct: usize,
+    /// How many answers there are
+    pub answers: usize,
+    /// Whether the solution's spangram is the official one. `None` if the answers
+    /// don't mark a spangram.
+    pub spangram_hit: Option<bool>,
+}
+
+impl Accuracy {
+    /// Compare a solution to the answers by word, ignoring case and the spaces in
+    /// phrases. A word found twice only counts once unless it is an answer twice.
+    pub fn compare(solution: &Solution, answers: &[Answer]) -> Self {
+        let normalize = |word: &str| squash(&word.to_lowercase());
+        let mut left: HashMap<String, usize> = HashMap::new();
+        for answer in answers {
+            *left.entry(normalize(&answer.word)).or_default() += 1;
+        }
+        let correct = solution
+            .words
+            .iter()
+            .filter(|word| match left.get_mut(&normalize(word)) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    true
+                }
+                _ => false,
+            })
+            .count();
+        let spangram_hit = answers.iter().find(|answer| answer.spangram).map(|answer| {
+            solution.spangram.is_some_and(|spangram| {
+                normalize(&solution.words[spangram]) == normalize(&answer.word)
+            })
+        });
+        Accuracy {
+            found: solution.words.len(),
+            correct,
+            answers: answers.len(),
+            spangram_hit,
+        }
+    }
+
+    /// The share of the solution's words that are answers, or zero if it has none
+    pub fn precision(&self) -> f64 {
+        ratio(self.correct, self.found)
+    }
+
+    /// The share of the answers the solution found, or zero if there are none
+    pub fn recall(&self) -> f64 {
+        ratio(self.correct, self.answers)
+    }
+}
+
+impl std::ops::AddAssign<&Accuracy> for Accuracy {
+    /// Add up the word counts, so precision and recall cover every word at once.
+    /// The spangram hits can't be added up, so they are left out.
+    fn add_assign(&mut self, other: &Accuracy) {
+        self.found += other.found;
+        self.correct += other.correct;
+        self.answers += other.answers;
+        self.spangram_hit = None;
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn answer(word: &str, spangram: bool) -> Answer {
+        Answer {
+            word: word.to_string(),
+            path: vec![],
+            spangram,
+        }
+    }
+
+    fn solution(words: &[&str], spangram: Option<usize>) -> Solution {
+        Solution {
+            words: words.iter().map(|word| word.to_string()).collect(),
+            paths: vec![],
+            spangram,
+        }
+    }
+
+    #[rstest]
+    #[case(&["tango", "waltz", "dancemoves"], Some(2), 3, Some(true))]
+    #[case(&["tango", "tango", "salsa"], None, 1, Some(false))]
+    #[case(&["tan", "go", "waltz", "dancemoves"], Some(2), 2, Some(false))]
+    #[case(&[], None, 0, Some(false))]
+    fn test_compare(
+        #[case] words: &[&str],
+        #[case] spangram: Option<usize>,
+        #[case] correct: usize,
+        #[case] spangram_hit: Option<bool>,
+    ) {
+        let answers = vec![
+            answer("Tango", false),
+            answer("waltz", false),
+            answer("dance moves", true),
+        ];
+        let accuracy = Accuracy::compare(&solution(words, spangram), &answers);
+        assert_eq!(
+            Accuracy {
+                found: words.len(),
+                correct,
+                answers: 3,
+                spangram_hit
+            },
+            accuracy
+        );
+    }
+
+    #[test]
+    fn test_totals() {
+        let mut total = Accuracy::compare(
+            &solution(&["tango", "waltz"], None),
+            &[answer("tango", false), answer("salsa", false)],
+        );
+        assert_eq!(None, total.spangram_hit);
+        assert_eq!(0.5, total.precision());
+
+        total += &Accuracy::compare(
+            &solution(&["rumba", "salsa"], None),
+            &[answer("rumba", false)],
+        );
+        assert_eq!(0.5, total.precision());
+        assert_eq!(2.0 / 3.0, total.recall());
+        assert_eq!(0.0, Accuracy::default().recall());
+    }
+}
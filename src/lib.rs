@@ -1,3 +1,38 @@
+pub mod board;
+pub mod categories;
+pub mod constraints;
+pub mod embeddings;
+pub mod evaluate;
+pub mod prior;
+pub mod puzzle;
+pub mod recover;
+pub mod scoring;
+pub mod solver;
+pub mod spangram;
+pub mod verify;
+pub mod words;
+
+pub use board::Board;
+pub use categories::{rank_categories, Category};
+pub use constraints::{apply_hint, exclude_placements};
+pub use embeddings::Embeddings;
+pub use evaluate::Accuracy;
+pub use prior::{ArchivedPuzzle, Prior};
+pub use puzzle::{Answer, Puzzle, PuzzleError};
+pub use recover::{ambiguous_answers, recover_paths, RecoverError};
+pub use scoring::{
+    CategoryScorer, CommandScorer, EmbeddingScorer, FrequencyScorer, Scorer, ShapeScorer,
+    WeightedScorer,
+};
+pub use solver::{
+    count_solutions, find_found_word, solve, solve_all, solve_each, CancelToken, Progress,
+    Solution, SolveControl, SolveError, SolveStats,
+};
+pub use spangram::{
+    find_compound_spangrams, find_spangrams, rank_spangrams, SpangramCandidate, SpangramKind,
+};
+pub use verify::{verify_solution, Violation};
+
 #[inline]
 pub fn two_words_no_diag_overlap(
     word1: usize,
@@ -33,6 +68,52 @@ pub fn two_words_no_diag_overlap(
     true
 }
 
+/// A mask of every spot that is the top-left corner of a 2x2 square on the board,
+/// which is every spot not in the last column or the last row
+pub fn square_corner_mask(board_width: usize, board_height: usize) -> usize {
+    (0..board_height - 1)
+        .flat_map(|y| (0..board_width - 1).map(move |x| y * board_width + x))
+        .fold(0, |acc, idx| acc | (1 << idx))
+}
+
+/// The 2x2 squares, marked by their top-left spot, where `word` covers both ends of
+/// the top-left to bottom-right diagonal, and where it covers both ends of the
+/// top-right to bottom-left diagonal. `corner_mask` comes from [`square_corner_mask`].
+#[inline]
+pub fn diagonals(word: usize, board_width: usize, corner_mask: usize) -> (usize, usize) {
+    let w = board_width;
+    let backslash = word & (word >> (w + 1)) & corner_mask;
+    let slash = (word >> 1) & (word >> w) & corner_mask;
+    (backslash, slash)
+}
+
+/// The same check as [`two_words_no_diag_overlap`], but looking at every 2x2 square
+/// at once by shifting the words so each corner of a square lines up with its
+/// top-left spot. `corner_mask` should come from [`square_corner_mask`], so that it
+/// can be worked out once per board rather than on every call.
+#[inline]
+pub fn two_words_no_diag_overlap_shifted(
+    word1: usize,
+    word2: usize,
+    board_width: usize,
+    corner_mask: usize,
+) -> bool {
+    let (backslash1, slash1) = diagonals(word1, board_width, corner_mask);
+    let (backslash2, slash2) = diagonals(word2, board_width, corner_mask);
+    // An X is one word on each diagonal of the same square
+    (backslash1 & slash2) | (backslash2 & slash1) == 0
+}
+
+/// Whether `word` touches both the left and right sides of the board, or both the
+/// top and bottom, as a spangram must
+pub fn is_spangram(word: usize, board_width: usize, board_height: usize) -> bool {
+    let column = (0..board_height).fold(0, |acc, y| acc | (1 << (y * board_width)));
+    let row = (1 << board_width) - 1;
+    let last_row = row << (board_width * (board_height - 1));
+    (word & column != 0 && word & (column << (board_width - 1)) != 0)
+        || (word & row != 0 && word & last_row != 0)
+}
+
 /// For each word in `existing_words`, check if `new_word` doesn't
 /// cross any of them diagonally
 pub fn no_diagonal_overlap(
@@ -77,4 +158,67 @@ mod tests {
             expected
         );
     }
+
+    #[rstest]
+    #[case(2, 2, 0b1)]
+    #[case(3, 3, 0b000011011)]
+    #[case(4, 2, 0b00000111)]
+    #[case(2, 4, 0b00010101)]
+    fn test_square_corner_mask(
+        #[case] board_width: usize,
+        #[case] board_height: usize,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(square_corner_mask(board_width, board_height), expected);
+    }
+
+    #[rstest]
+    #[case(0b000000111, 3, 3, true)] // across the top
+    #[case(0b010010010, 3, 3, true)] // down the middle
+    #[case(0b000001011, 3, 3, false)] // only the left and top
+    #[case(0b000010010, 3, 3, false)] // doesn't reach the bottom
+    #[case(0b00001110, 4, 2, false)] // misses the left column
+    #[case(0b00011110, 4, 2, true)]
+    fn test_is_spangram(
+        #[case] word: usize,
+        #[case] board_width: usize,
+        #[case] board_height: usize,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_spangram(word, board_width, board_height), expected);
+    }
+
+    #[rstest]
+    #[case(0b000010001, 3, (0b000000001, 0))] // down and right from the corner
+    #[case(0b000001010, 3, (0, 0b000000001))] // down and left
+    #[case(0b000011011, 3, (0b000000001, 0b000000001))] // filled square
+    #[case(0b100010001, 3, (0b000010001, 0))] // long diagonal
+    #[case(0b000010100, 3, (0, 0b000000010))] // a square further along
+    fn test_diagonals(
+        #[case] word: usize,
+        #[case] board_width: usize,
+        #[case] expected: (usize, usize),
+    ) {
+        let corner_mask = square_corner_mask(board_width, 3);
+        assert_eq!(diagonals(word, board_width, corner_mask), expected);
+    }
+
+    #[rstest]
+    #[case(3, 3)]
+    #[case(4, 3)]
+    #[case(3, 4)]
+    fn test_shifted_matches_loop(#[case] board_width: usize, #[case] board_height: usize) {
+        // Every pair of words on a small board gives the same answer both ways
+        let corner_mask = square_corner_mask(board_width, board_height);
+        let size = board_width * board_height;
+        for word1 in 0..(1 << size) {
+            for word2 in (0..(1 << size)).filter(|word2| word1 & word2 == 0) {
+                assert_eq!(
+                    two_words_no_diag_overlap(word1, word2, board_width, board_height),
+                    two_words_no_diag_overlap_shifted(word1, word2, board_width, corner_mask),
+                    "{word1:b} {word2:b}"
+                );
+            }
+        }
+    }
 }
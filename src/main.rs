@@ -1,319 +1,1498 @@
-use std::fs;
-
-use clap::Parser;
-use smallvec::{smallvec, SmallVec};
-
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rayon::prelude::*;
+use serde::Serialize;
+
+use strands::words::{
+    dictionary_words, is_phrase, parse_frequencies, parse_phrases, phrase_parts, squash,
+};
 use strands::*;
 
+/// The size of a Strands board
+const BOARD_W: usize = 6;
+const BOARD_H: usize = 8;
+
+/// Set when stdout is kept for JSON, so that [`message!`] writes to stderr instead
+static MESSAGES_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Print a message about how solving is going: to stdout, or to stderr when the
+/// solutions are printed as JSON
+macro_rules! message {
+    ($($arg:tt)*) => {
+        if MESSAGES_TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    board: BoardArgs,
+
+    #[command(flatten)]
+    solve: SolveArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the words that could be the spangram, best guesses first
+    Spangrams(SpangramArgs),
+    /// List the categories with the most words on the board, and whether the board
+    /// can be covered with words from just one of them
+    Themes(ThemeArgs),
+    /// Learn how likely words are to be answers from an archive of past puzzles
+    Train(TrainArgs),
+    /// Solve every puzzle file in a directory and compare the results to their
+    /// official answers
+    Batch(BatchArgs),
+    /// Find the paths of a puzzle's answers from just their words, and save them to
+    /// the puzzle file
+    Paths(PathsArgs),
+    /// Check a proposed solution: the words fit along their paths, cover the board
+    /// without overlapping or crossing, and one of them is a spangram
+    Check(CheckArgs),
+}
+
+/// The board, and the words to look for on it
+#[derive(Args, Debug)]
+struct BoardArgs {
     /// Each row of letters, separated by a space. E.g. "abc def ghi".
     /// There should be exactly 8 groups of 6 letters
-    #[arg()]
-    letters: String,
+    #[arg(required_unless_present = "puzzle")]
+    letters: Option<String>,
+
+    /// A puzzle file to take the board from, instead of the letters. It can also
+    /// give the clue and the number of words. JSON, or TOML if it ends in .toml
+    #[arg(long, conflicts_with = "letters")]
+    puzzle: Option<String>,
 
     /// The dictionary file to use. By default, use the american english dictionary file
     #[arg(short = 'd', long, default_value = "american_english_dictionary.txt")]
     dictionary_file: String,
+}
 
+#[derive(Args, Debug)]
+struct SolveArgs {
     /// Minimum number of words
+    #[arg(long)]
+    min_words: Option<usize>,
+
+    /// Maximum number of words. With --puzzle, the puzzle's number of theme words
+    /// unless this is given
+    #[arg(long, required_unless_present = "puzzle")]
+    max_words: Option<usize>,
+
+    /// Print every set of words that covers the board, instead of just the first
+    #[arg(long, conflicts_with = "count")]
+    all: bool,
+
+    /// Only count how many sets of words cover the board
+    #[arg(long)]
+    count: bool,
+
+    /// Number of threads to search with. By default, use one per core
+    #[arg(short = 't', long, default_value_t = 0)]
+    threads: usize,
+
+    /// Give up searching after this many seconds
+    #[arg(long, value_parser = parse_seconds)]
+    timeout: Option<Duration>,
+
+    /// Require exactly one spangram, a word touching two opposite sides of the board
+    #[arg(long)]
+    spangram: bool,
+
+    /// Megabytes of memory to spend remembering positions that can't be solved.
+    /// Zero turns this off
+    #[arg(long, default_value_t = 256)]
+    memo_mb: usize,
+
+    /// A word already found, and its path, like "hustle@r2c0-r3c0-...". It is
+    /// placed before the search starts. The path can be left off if the word only
+    /// fits one way. Can be given more than once
+    #[arg(long)]
+    found: Vec<String>,
+
+    /// A word the game rejected, so it is left out of every solution. Can be given
+    /// more than once
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// A path the game rejected, like "r0c0-r0c1-...". Whatever word it spells is
+    /// left out of every solution. Can be given more than once
+    #[arg(long)]
+    exclude_path: Vec<String>,
+
+    /// The spots of one word, in any order, as shown by the game's hint, like
+    /// "r2c1,r2c2,r3c2". Exactly one word covers them. Can be given more than once
+    #[arg(long)]
+    hint: Vec<String>,
+
+    /// The spots of the spangram, in any order, once the game has revealed them.
+    /// Implies --spangram
+    #[arg(long)]
+    spangram_hint: Option<String>,
+
+    /// The puzzle's theme clue. Unless --score is given, words are tried and
+    /// solutions ranked by how closely their words relate to it and to each other
+    #[arg(long, requires = "embeddings")]
+    clue: Option<String>,
+
+    /// A GloVe or word2vec word vector file in text format, for --clue or the
+    /// puzzle's clue
+    #[arg(long)]
+    embeddings: Option<String>,
+
+    /// A category word list, one word per line. Every word but the spangram must
+    /// come from it. Implies --spangram
+    #[arg(long)]
+    category: Option<String>,
+
+    /// Score words and covers with a heuristic, as KIND=WEIGHT or KIND:FILE=WEIGHT.
+    /// The kinds are frequency:FILE (a word frequency list), clue (needs --clue),
+    /// shape (straight paths) and category:FILE (a category word list). Words are
+    /// tried best first, and solutions are ranked by the weighted total. Can be given
+    /// more than once
+    #[arg(long)]
+    score: Vec<String>,
+
+    /// A command to score words with, run with `sh -c`. It reads one JSON request
+    /// per line, like {"kind": "placement", "word": "dance", "path": [[0, 1], ...]}
+    /// or {"kind": "cover", "words": [...], "paths": [...]}, and answers each with a
+    /// line like {"score": 0.75}. Its scores are added to any from --score, or to
+    /// the clue's if --score isn't given
+    #[arg(long)]
+    scorer_cmd: Option<String>,
+
+    /// A prior learned by the train command. Words are scored by how likely they are
+    /// to be answers, added to any scores from --score, or to the clue's if --score
+    /// isn't given
+    #[arg(long)]
+    prior: Option<String>,
+
+    /// Save the board, the clue and the solution found to a puzzle file. JSON, or
+    /// TOML if it ends in .toml
+    #[arg(long)]
+    save: Option<String>,
+
+    /// How to print the solutions. json prints every word with its path as
+    /// [row, column] pairs, whether it is the spangram, the score, and the time and
+    /// statistics of the search. jsonl prints one solution per line, as soon as it
+    /// is found with --all. Other messages go to stderr
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+    Jsonl,
+}
+
+#[derive(Args, Debug)]
+struct SpangramArgs {
+    #[command(flatten)]
+    board: BoardArgs,
+
+    /// A file of word frequencies, each line a word and how often it is used, for
+    /// ranking candidates of the same length
+    #[arg(short = 'f', long)]
+    frequencies: Option<String>,
+
+    /// A file of extra phrases to look for, one per line with its words separated
+    /// by spaces or hyphens, e.g. "dance moves"
+    #[arg(short = 'p', long)]
+    phrases: Option<String>,
+
+    /// Also look for two or three dictionary words run together along a path
+    #[arg(long)]
+    compounds: bool,
+
+    /// How many candidates to show
+    #[arg(short = 'n', long, default_value_t = 10)]
+    top: usize,
+}
+
+#[derive(Args, Debug)]
+struct ThemeArgs {
+    #[command(flatten)]
+    board: BoardArgs,
+
+    /// Category word lists, one word per line, named after the file. A directory
+    /// adds every .txt file in it
+    #[arg(short = 'c', long, required = true, num_args = 1..)]
+    categories: Vec<String>,
+
+    /// Maximum number of words in a cover
+    #[arg(long, default_value_t = 12)]
+    max_words: usize,
+
+    /// Give up looking for a cover from each category after this many seconds
+    #[arg(long, value_parser = parse_seconds)]
+    timeout: Option<Duration>,
+}
+
+#[derive(Args, Debug)]
+struct TrainArgs {
+    /// The archive of past puzzles, one per line as JSON like
+    /// {"letters": "abcdef ghijkl ...", "answers": ["word", ...]}
     #[arg()]
-    min_words: usize,
+    archive: String,
+
+    /// The dictionary file to use. By default, use the american english dictionary file
+    #[arg(short = 'd', long, default_value = "american_english_dictionary.txt")]
+    dictionary_file: String,
 
-    /// Maximum number of words
+    /// Where to save the prior, for --prior
+    #[arg(short = 'o', long, default_value = "prior.json")]
+    output: String,
+}
+
+#[derive(Args, Debug)]
+struct BatchArgs {
+    /// A directory of puzzle files with their answers. Every .json and .toml file
+    /// in it is solved
     #[arg()]
-    max_words: usize,
+    dir: String,
+
+    /// The dictionary file to use. By default, use the american english dictionary file
+    #[arg(short = 'd', long, default_value = "american_english_dictionary.txt")]
+    dictionary_file: String,
+
+    /// How to print the report
+    #[arg(long, value_enum, default_value_t = ReportFormat::Csv)]
+    format: ReportFormat,
+
+    /// Number of threads to search with. By default, use one per core
+    #[arg(short = 't', long, default_value_t = 0)]
+    threads: usize,
+
+    /// Give up on each puzzle after this many seconds
+    #[arg(long, value_parser = parse_seconds)]
+    timeout: Option<Duration>,
+
+    /// Megabytes of memory each puzzle may spend remembering positions that can't
+    /// be solved. Several puzzles are solved at once
+    #[arg(long, default_value_t = 64)]
+    memo_mb: usize,
 }
 
-#[derive(Debug, PartialEq)]
-struct Board {
-    letters: Vec<char>,
-    w: usize,
-    h: usize,
+#[derive(Args, Debug)]
+struct PathsArgs {
+    /// The puzzle file, with the words of its answers. JSON, or TOML if it ends in
+    /// .toml
+    #[arg()]
+    puzzle: String,
+
+    /// Where to save the puzzle with the paths found. By default, the puzzle file
+    /// itself
+    #[arg(short = 'o', long)]
+    output: Option<String>,
 }
 
-impl Board {
-    /// Goes from groups of six letters separated by a space, to a flat array
-    fn parse_flat_board(letters: &str, width: usize, height: usize) -> Board {
-        let mut bletters = Vec::with_capacity(width * height);
+#[derive(Args, Debug)]
+struct CheckArgs {
+    /// A puzzle file whose answers are the proposed words and their paths. JSON, or
+    /// TOML if it ends in .toml
+    #[arg()]
+    puzzle: String,
+}
 
-        for c in letters.replace(' ', "").chars() {
-            bletters.push(c);
-        }
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ReportFormat {
+    Csv,
+    Json,
+}
 
-        Board {
-            letters: bletters,
-            w: width,
-            h: height,
-        }
+/// The most dictionary words joined together by `--compounds`
+const MAX_COMPOUND_PARTS: usize = 3;
+
+/// The exit code for each way solving can fail. Clap already uses 2 for bad
+/// arguments.
+fn exit_code(err: &SolveError) -> i32 {
+    match err {
+        SolveError::NoSolution => 3,
+        SolveError::UncoverableCell(_) => 4,
+        SolveError::TooManyWords { .. } => 5,
+        SolveError::Timeout => 6,
+        SolveError::Cancelled => 7,
+        SolveError::NotACandidate(_)
+        | SolveError::FoundConflict(_)
+        | SolveError::AmbiguousFound { .. } => 8,
     }
+}
 
-    /// Return a list of neighbors. Works as like a 2d array of width `w` and height `h`.
-    /// Gets diagonal neighbors too.
-    ///
-    /// Copied from
-    /// <https://stackoverflow.com/questions/9355537/finding-neighbors-of-2d-array-when-represented-as-1d-array>
-    ///
-    /// Could perhaps write a version of this that takes in a mutable bit array, sets
-    /// everything to zero, then sets the right ones to true.
-    fn get_neighbors(&self, i: usize) -> Vec<usize> {
-        let size = self.w * self.h;
-        let mut neighbors: Vec<usize> = Vec::new();
-
-        if i.checked_sub(self.w).is_some() {
-            neighbors.push(i - self.w); // north
-        }
+/// A path written out as the names of its spots, like "r0c0-r0c1-r1c1"
+fn path_name(board: &Board, path: &[usize]) -> String {
+    let names: Vec<String> = path.iter().map(|&idx| board.cell_name(idx)).collect();
+    names.join("-")
+}
 
-        if i % self.w != 0 {
-            neighbors.push(i - 1); // west
-        }
+/// Read a `--timeout`, a number of seconds like "2.5"
+fn parse_seconds(secs: &str) -> Result<Duration, String> {
+    let secs: f64 = secs
+        .parse()
+        .map_err(|_| format!("{secs} isn't a number of seconds"))?;
+    Duration::try_from_secs_f64(secs).map_err(|_| format!("can't wait {secs} seconds"))
+}
 
-        if (i + 1) % self.w != 0 {
-            neighbors.push(i + 1); // east
-        }
+/// When a solve given `timeout` should stop. `None` if that is too far off to
+/// matter.
+fn deadline(timeout: Duration) -> Option<Instant> {
+    Instant::now().checked_add(timeout)
+}
 
-        if (i + self.w) < size {
-            neighbors.push(i + self.w); // south
-        }
+/// The bytes `--memo-mb` allows, or a usage error if that many don't fit in a
+/// `usize`
+fn memo_limit(memo_mb: usize) -> usize {
+    memo_mb
+        .checked_mul(1024 * 1024)
+        .unwrap_or_else(|| usage_error(format!("--memo-mb {memo_mb} is too much memory")))
+}
 
-        if (i.checked_sub(self.w + 1).is_some()) & (i % self.w != 0) {
-            neighbors.push(i - self.w - 1); // northwest
-        }
+/// Read a path written out like "r0c0-r0c1-r1c1", or a set of spots separated by
+/// `separator`
+fn parse_path(cells: &str, separator: char, board: &Board) -> Result<Vec<usize>, String> {
+    cells
+        .split(separator)
+        .map(|name| {
+            board
+                .parse_cell(name)
+                .ok_or_else(|| format!("{name} isn't a spot on the board"))
+        })
+        .collect()
+}
+
+/// Print an error about the arguments and stop
+fn usage_error(err: String) -> ! {
+    eprintln!("{err}");
+    std::process::exit(2);
+}
 
-        if ((i + 1).checked_sub(self.w).is_some()) & ((i + 1) % self.w != 0) {
-            neighbors.push(i + 1 - self.w); // northeast
+/// Read a `--found` word, and its path if it has one
+fn parse_found(found: &str, board: &Board) -> Result<(String, Option<Vec<usize>>), String> {
+    match found.split_once('@') {
+        Some((word, cells)) => Ok((word.to_string(), Some(parse_path(cells, '-', board)?))),
+        None => Ok((found.to_string(), None)),
+    }
+}
+
+/// What is wrong with the `--found` word `found`, as from [`find_found_word`]
+fn found_error(board: &Board, found: &str, err: &SolveError) -> String {
+    match err {
+        SolveError::NotACandidate(word) => match found.split_once('@') {
+            Some((_, cells)) => format!("{word} isn't a candidate along {cells}"),
+            None => format!("{word} doesn't fit anywhere on the board"),
+        },
+        SolveError::AmbiguousFound { word, paths } => {
+            let options: Vec<String> = paths
+                .iter()
+                .map(|path| format!("  {word}@{}", path_name(board, path)))
+                .collect();
+            format!(
+                "{word} fits {} ways, pick one of:\n{}",
+                paths.len(),
+                options.join("\n")
+            )
         }
+        _ => err.to_string(),
+    }
+}
+
+/// Print a solution's words, marking the spangram if there is one
+fn print_solution(solution: &Solution) {
+    match solution.spangram {
+        Some(spangram) => println!(
+            "{:?} (spangram: {})",
+            solution.words, solution.words[spangram]
+        ),
+        None => println!("{:?}", solution.words),
+    }
+}
 
-        if ((i + self.w - 1) < size) & (i % self.w != 0) {
-            neighbors.push(i + self.w - 1); // southwest
+/// Read category word lists from files, or from every .txt file in a directory
+fn load_categories(paths: &[String]) -> Vec<Category> {
+    let mut files = vec![];
+    for path in paths.iter().map(Path::new) {
+        if path.is_dir() {
+            let mut in_dir: Vec<PathBuf> = fs::read_dir(path)
+                .expect("Unable to read directory")
+                .map(|entry| entry.expect("Unable to read directory").path())
+                .filter(|file| file.extension().is_some_and(|ext| ext == "txt"))
+                .collect();
+            in_dir.sort();
+            files.extend(in_dir);
+        } else {
+            files.push(path.to_path_buf());
         }
+    }
+    files
+        .iter()
+        .map(|file| {
+            let name = file.file_stem().unwrap_or_default().to_string_lossy();
+            Category::parse(
+                &name,
+                &fs::read_to_string(file).expect("Unable to read file"),
+            )
+        })
+        .collect()
+}
+
+/// How many candidate words closest to the clue to print
+const CLOSEST_WORDS: usize = 10;
+
+/// Load the vectors for the candidates and the clue, and print the candidates
+/// closest to it
+fn load_clue(
+    clue: &str,
+    file: &str,
+    words_that_fit: &[Vec<(String, Vec<usize>)>],
+) -> (Embeddings, Vec<f32>) {
+    let mut wanted: HashSet<String> = words_that_fit
+        .iter()
+        .flatten()
+        .map(|(word, _)| word.clone())
+        .collect();
+    wanted.extend(
+        clue.split(|c: char| !c.is_alphanumeric())
+            .map(str::to_lowercase),
+    );
+    let reader = BufReader::new(File::open(file).expect("Unable to read file"));
+    let embeddings =
+        Embeddings::read(reader, |word| wanted.contains(word)).expect("Unable to read file");
+    let Some(clue_vector) = embeddings.text_vector(clue) else {
+        usage_error(format!(
+            "None of the words in the clue \"{clue}\" are in {file}"
+        ));
+    };
+
+    let mut closest: Vec<(f32, &String)> = wanted
+        .iter()
+        .filter(|word| embeddings.get(word).is_some())
+        .map(|word| (embeddings.similarity(word, &clue_vector), word))
+        .collect();
+    closest.sort_by(|(a, word_a), (b, word_b)| b.total_cmp(a).then(word_a.cmp(word_b)));
+    let closest: Vec<String> = closest
+        .iter()
+        .take(CLOSEST_WORDS)
+        .map(|(score, word)| format!("{word} ({score:.2})"))
+        .collect();
+    message!("Closest words to the clue: {}", closest.join(", "));
+    (embeddings, clue_vector)
+}
 
-        if ((i + self.w + 1) < size) & ((i + 1) % self.w != 0) {
-            neighbors.push(i + self.w + 1); // southeast
+/// A scorer and the weight its scores count with
+type Weighted = (f64, Arc<dyn Scorer>);
+
+/// Read a `--score` heuristic and its weight
+fn parse_score(spec: &str, clue: Option<&(Embeddings, Vec<f32>)>) -> Result<Weighted, String> {
+    let (kind, weight) = spec
+        .rsplit_once('=')
+        .ok_or_else(|| format!("{spec} should look like KIND=WEIGHT"))?;
+    let weight: f64 = weight
+        .parse()
+        .map_err(|_| format!("{weight} isn't a weight"))?;
+    let (kind, file) = match kind.split_once(':') {
+        Some((kind, file)) => (kind, Some(file)),
+        None => (kind, None),
+    };
+    let read = |file: &str| fs::read_to_string(file).expect("Unable to read file");
+    let scorer: Arc<dyn Scorer> = match (kind, file) {
+        ("frequency", Some(file)) => Arc::new(FrequencyScorer::new(parse_frequencies(&read(file)))),
+        ("category", Some(file)) => {
+            let name = Path::new(file)
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy();
+            Arc::new(CategoryScorer(Category::parse(&name, &read(file))))
+        }
+        ("clue", None) => {
+            let (embeddings, clue) = clue.ok_or("Scoring by clue needs --clue")?;
+            Arc::new(EmbeddingScorer::new(embeddings.clone(), clue.clone()))
+        }
+        ("shape", None) => Arc::new(ShapeScorer::new(BOARD_W)),
+        ("frequency" | "category", None) => {
+            return Err(format!("{kind} scores need a file, like {kind}:FILE=1"))
         }
+        _ => return Err(format!("{spec} isn't a kind of score")),
+    };
+    Ok((weight, scorer))
+}
 
-        neighbors
+/// The `--score` heuristics, or the clue on its own if none are given
+fn base_scorers(
+    specs: &[String],
+    clue: Option<&(Embeddings, Vec<f32>)>,
+) -> Result<Vec<Weighted>, String> {
+    if specs.is_empty() {
+        return Ok(clue
+            .map(|(embeddings, clue)| {
+                let scorer = EmbeddingScorer::new(embeddings.clone(), clue.clone());
+                (1.0, Arc::new(scorer) as Arc<dyn Scorer>)
+            })
+            .into_iter()
+            .collect());
     }
+    specs.iter().map(|spec| parse_score(spec, clue)).collect()
+}
 
-    /// From a given starting point on the board, what words can be formed?
-    fn find_valid_words_from_start(
-        &self,
-        start_point: usize,
-        words: &[&str],
-    ) -> Vec<(String, Vec<usize>)> {
-        let mut result: Vec<(String, Vec<usize>)> = Vec::new();
+/// Add up the scores of every scorer with its weight, if there are any
+fn combine_scorers(scorers: Vec<Weighted>) -> Option<Arc<dyn Scorer>> {
+    if scorers.is_empty() {
+        return None;
+    }
+    let weighted = scorers
+        .into_iter()
+        .fold(WeightedScorer::new(), |weighted, (weight, scorer)| {
+            weighted.with(weight, scorer)
+        });
+    Some(Arc::new(weighted))
+}
 
-        let start_spot = vec![start_point];
-        let new_words: Vec<&str> = words
-            .iter()
-            .filter(|w| w.starts_with(self.letters[start_point]))
-            .copied()
-            .collect();
+/// How often to print progress while solving
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Print progress to stderr every [`PROGRESS_INTERVAL`] until `done` is set
+fn report_progress(progress: &Progress, board_size: usize, done: &AtomicBool) {
+    let start = Instant::now();
+    let mut next_report = start + PROGRESS_INTERVAL;
+    while !done.load(Ordering::Relaxed) {
+        std::thread::sleep(Duration::from_millis(50));
+        if Instant::now() < next_report {
+            continue;
+        }
+        next_report += PROGRESS_INTERVAL;
+        eprintln!(
+            "[{:.0}s] placed {} words, at depth {}, best cover so far {}/{board_size} spots",
+            start.elapsed().as_secs_f64(),
+            progress.nodes(),
+            progress.depth(),
+            progress.best_coverage(),
+        );
+    }
+}
 
-        result.extend(self.find_next(&new_words, &start_spot, start_point));
-        result
+/// Find every placement of `words` on the board
+fn find_words_that_fit(board: &Board, words: &[&str]) -> Vec<Vec<(String, Vec<usize>)>> {
+    let filter_start = std::time::Instant::now();
+    let all_words_that_fit = board.find_words_that_fit(words);
+    let filter_time = filter_start.elapsed().as_millis();
+    message!("Filtering words for all spots took {filter_time}ms");
+    message!(
+        "Found {} possible words",
+        all_words_that_fit.iter().flatten().count()
+    );
+    all_words_that_fit
+}
+
+fn main() {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Spangrams(args)) => list_spangrams(&args),
+        Some(Command::Themes(args)) => list_themes(&args),
+        Some(Command::Train(args)) => train_prior(&args),
+        Some(Command::Batch(args)) => run_batch(&args),
+        Some(Command::Paths(args)) => find_answer_paths(&args),
+        Some(Command::Check(args)) => check_solution(&args),
+        None => solve_board(&cli.board, &cli.solve),
     }
+}
 
-    /// A recursive method for finding valid words
-    fn find_next(
-        &self,
-        words: &[&str],
-        start_spots: &[usize],
-        current_board_position: usize,
-    ) -> Vec<(String, Vec<usize>)> {
-        // If no more words, end
-        if words.is_empty() {
-            return vec![];
-        }
+/// Read the board from the letters or the puzzle file, along with the puzzle
+fn load_board(args: &BoardArgs) -> (Board, Option<Puzzle>) {
+    let Some(file) = &args.puzzle else {
+        let letters = args.letters.as_deref().expect("clap asks for the letters");
+        return (Board::parse_flat_board(letters, BOARD_W, BOARD_H), None);
+    };
+    let puzzle = Puzzle::load(Path::new(file))
+        .unwrap_or_else(|err| usage_error(format!("Unable to load {file}: {err}")));
+    if (puzzle.width, puzzle.height) != (BOARD_W, BOARD_H) {
+        usage_error(format!(
+            "{file} is {}x{}, but only {BOARD_W}x{BOARD_H} boards are supported",
+            puzzle.width, puzzle.height
+        ));
+    }
+    (puzzle.board(), Some(puzzle))
+}
 
-        // Otherwise, loop over the neighbors, and return the results
-        let mut result = Vec::new();
-        let nbr_inds = self.get_neighbors(current_board_position);
-        for nbr_idx in nbr_inds {
-            // If this letter is already seen in the `start_spots`, continue
-            if start_spots.contains(&nbr_idx) {
-                continue;
-            }
-            // What word is created by adding this neighbor?
-            let word = self.make_word_from_inds(start_spots, nbr_idx);
-
-            // If adding this neighbor makes a complete word, push to result
-            if words.contains(&word.as_str()) {
-                let mut positions = start_spots.to_vec();
-                positions.push(nbr_idx);
-                result.push((word.clone(), positions));
-            }
+/// Print the most likely spangrams, with their paths drawn on the board
+fn list_spangrams(args: &SpangramArgs) {
+    let (board, _) = load_board(&args.board);
+
+    // Phrases are looked for with their letters run together, as on the board
+    let text = fs::read_to_string(&args.board.dictionary_file).expect("Unable to read file");
+    let phrase_list = match &args.phrases {
+        Some(file) => parse_phrases(&fs::read_to_string(file).expect("Unable to read file")),
+        None => vec![],
+    };
+    let mut entries = dictionary_words(&text);
+    entries.extend(phrase_list.iter().map(String::as_str));
+    let phrases: HashMap<String, Vec<String>> = entries
+        .iter()
+        .filter(|entry| is_phrase(entry))
+        .map(|&entry| (squash(entry), phrase_parts(entry)))
+        .collect();
+    let squashed: Vec<String> = entries.iter().map(|entry| squash(entry)).collect();
+    let mut valid_words: Vec<&str> = squashed.iter().map(String::as_str).collect();
+    valid_words.sort_unstable();
+    valid_words.dedup();
 
-            // What words are left for this word?
-            let rem_words: Vec<&str> = words
-                .iter()
-                .filter(|w| w.starts_with(&word))
-                .copied()
-                .collect();
+    let frequencies = match &args.frequencies {
+        Some(file) => parse_frequencies(&fs::read_to_string(file).expect("Unable to read file")),
+        None => HashMap::new(),
+    };
+
+    let all_words_that_fit = find_words_that_fit(&board, &valid_words);
+    let mut candidates = find_spangrams(
+        &all_words_that_fit,
+        BOARD_W,
+        BOARD_H,
+        &frequencies,
+        &phrases,
+    );
+    if args.compounds {
+        let compounds =
+            find_compound_spangrams(&board, &valid_words, MAX_COMPOUND_PARTS, &frequencies);
+        println!("Found {} compound words", compounds.len());
+        candidates.extend(compounds);
+        rank_spangrams(&mut candidates);
+    }
 
-            // Quit if none left
-            if rem_words.is_empty() {
-                continue;
+    println!("\nFound {} spangram candidates", candidates.len());
+    for (rank, candidate) in candidates.iter().take(args.top).enumerate() {
+        let mut notes = vec![format!("{} letters", candidate.word.len())];
+        match candidate.kind {
+            SpangramKind::Word => {}
+            SpangramKind::Phrase => notes.push(format!("phrase \"{}\"", candidate.parts.join(" "))),
+            SpangramKind::Compound => {
+                notes.push(format!("compound of {}", candidate.parts.join(" + ")))
             }
-
-            // Call again from this neighbor position and push to the the result
-            let mut new_spots: Vec<usize> = start_spots.to_vec();
-            new_spots.push(nbr_idx);
-            result.extend(self.find_next(&rem_words, &new_spots, nbr_idx));
         }
-        result
+        if candidate.frequency > 0 {
+            notes.push(format!("frequency {}", candidate.frequency));
+        }
+        println!("\n{}. {} ({})", rank + 1, candidate.word, notes.join(", "));
+        println!("{}", board.draw_path(&candidate.path));
     }
+}
+
+/// Print the categories with the most words on the board, and try covering the
+/// board with each one
+fn list_themes(args: &ThemeArgs) {
+    let (board, _) = load_board(&args.board);
+    let words = fs::read_to_string(&args.board.dictionary_file).expect("Unable to read file");
+    let all_words_that_fit = find_words_that_fit(&board, &dictionary_words(&words));
+    let categories = load_categories(&args.categories);
 
-    /// Given a set of indices, create a new word from the letters at those indices
-    fn make_word_from_inds(&self, inds_so_far: &[usize], new_ind: usize) -> String {
-        let mut word: String = inds_so_far.iter().map(|idx| self.letters[*idx]).collect();
-        word.push(self.letters[new_ind]);
-        word
+    let ranked = rank_categories(&categories, &all_words_that_fit);
+    println!(
+        "\n{} of {} categories have words on the board",
+        ranked.len(),
+        categories.len()
+    );
+    for (category, words) in ranked {
+        println!(
+            "\n{}: {} words ({})",
+            category.name,
+            words.len(),
+            words.join(", ")
+        );
+        let mut words_that_fit = all_words_that_fit.clone();
+        category.restrict(&mut words_that_fit, BOARD_W, BOARD_H);
+        let control = SolveControl {
+            deadline: args.timeout.and_then(deadline),
+            spangram: true,
+            ..Default::default()
+        };
+        let mut stats = SolveStats::default();
+        match solve(
+            &words_that_fit,
+            args.max_words,
+            BOARD_W,
+            BOARD_H,
+            &control,
+            &mut stats,
+        ) {
+            Ok(solution) => {
+                print!("Covers the board: ");
+                print_solution(&solution);
+            }
+            Err(err @ (SolveError::Timeout | SolveError::Cancelled)) => {
+                println!("Search {err} before finding a cover")
+            }
+            Err(_) => println!("Can't cover the board"),
+        }
     }
 }
 
-/// Function to check if there is any overlap between the existing indices and new indices
-fn bit_overlaps(existing: usize, new_indices: usize) -> bool {
-    existing & new_indices != 0
-}
+/// Learn a prior from an archive of past puzzles and save it
+fn train_prior(args: &TrainArgs) {
+    let words = fs::read_to_string(&args.dictionary_file).expect("Unable to read file");
+    let valid_words = dictionary_words(&words);
+    let archive = fs::read_to_string(&args.archive).expect("Unable to read file");
+
+    let mut prior = Prior::new(BOARD_W);
+    let (mut puzzles, mut answers, mut missing) = (0, 0, 0);
+    for (line_idx, line) in archive.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let puzzle: ArchivedPuzzle = serde_json::from_str(line)
+            .unwrap_or_else(|err| usage_error(format!("Line {}: {err}", line_idx + 1)));
+        let board = Board::parse_rows(&puzzle.letters);
+        if board.w != BOARD_W || board.letters.len() != board.w * board.h {
+            println!(
+                "Skipping line {}: the board isn't {BOARD_W} letters wide",
+                line_idx + 1
+            );
+            continue;
+        }
+        let words_that_fit = board.find_words_that_fit(&valid_words);
+        let puzzle_answers: Vec<String> = puzzle
+            .answers
+            .iter()
+            .map(|answer| squash(&answer.to_lowercase()))
+            .collect();
+        let candidates: HashSet<&String> = words_that_fit
+            .iter()
+            .flatten()
+            .map(|(word, _)| word)
+            .collect();
+        missing += puzzle_answers
+            .iter()
+            .filter(|answer| !candidates.contains(answer))
+            .count();
+        answers += puzzle_answers.len();
+        prior.add_puzzle(&words_that_fit, &puzzle_answers);
+        puzzles += 1;
+    }
 
-/// Function to convert a &[usize] to a single usize representing the bits
-fn indices_to_bits(indices: &[usize]) -> usize {
-    indices.iter().fold(0, |acc, &idx| acc | (1 << idx))
+    let saved = serde_json::to_string(&prior).expect("Unable to save the prior");
+    fs::write(&args.output, saved).expect("Unable to write file");
+    println!(
+        "Learned from {puzzles} puzzles and {} different words, saved to {}",
+        prior.words.len(),
+        args.output
+    );
+    if missing > 0 {
+        println!("{missing} of {answers} answers weren't in the dictionary, so they were never candidates");
+    }
 }
 
-fn solve(
-    words_that_fit: &[Vec<(String, Vec<usize>)>],
-    max_len: usize,
-    board_w: usize,
-    board_h: usize,
-) -> Vec<String> {
-    // Convert all the Vec<usize> into single usizes
-    let condensed_words: Vec<usize> = words_that_fit
+/// Find the paths of a puzzle's answers and save them. Answers that fit more than
+/// one way are listed with each of their paths, and left without one.
+fn find_answer_paths(args: &PathsArgs) {
+    let mut puzzle = Puzzle::load(Path::new(&args.puzzle))
+        .unwrap_or_else(|err| usage_error(format!("Unable to load {}: {err}", args.puzzle)));
+    if puzzle.answers.is_empty() {
+        usage_error(format!("{} has no answers", args.puzzle));
+    }
+    if puzzle.width * puzzle.height > usize::BITS as usize {
+        usage_error(format!("{} is too big a board", args.puzzle));
+    }
+    let board = puzzle.board();
+    let words: Vec<String> = puzzle
+        .answers
         .iter()
-        .flat_map(|start_point| {
-            start_point
-                .iter()
-                .map(|(_, indices)| indices_to_bits(indices))
-        })
+        .map(|answer| answer.word.clone())
         .collect();
+    let spangram = puzzle.answers.iter().position(|answer| answer.spangram);
+    let solutions = match recover_paths(&board, &words, spangram) {
+        Ok(solutions) => solutions,
+        Err(err @ RecoverError::NotOnBoard(_)) => usage_error(err.to_string()),
+        Err(err) => {
+            println!("No paths found: {err}");
+            std::process::exit(3);
+        }
+    };
+
+    let ambiguous = ambiguous_answers(&solutions);
+    for (i, answer) in puzzle.answers.iter_mut().enumerate() {
+        if ambiguous.contains(&i) {
+            answer.path.clear();
+        } else {
+            answer.set_spots(&solutions[0].paths[i], board.w);
+            println!(
+                "{}: {}",
+                answer.word,
+                path_name(&board, &solutions[0].paths[i])
+            );
+        }
+    }
+    if !ambiguous.is_empty() {
+        println!(
+            "\nThe answers fit {} ways, so these were left without a path:",
+            solutions.len()
+        );
+        for &i in &ambiguous {
+            let mut paths: Vec<&Vec<usize>> = solutions
+                .iter()
+                .map(|solution| &solution.paths[i])
+                .collect();
+            paths.sort();
+            paths.dedup();
+            let names: Vec<String> = paths.iter().map(|path| path_name(&board, path)).collect();
+            println!("{}: {}", words[i], names.join(" or "));
+        }
+    }
 
-    // Get just the string out
-    let flattened_words_that_fit: Vec<String> = words_that_fit
+    let output = args.output.as_ref().unwrap_or(&args.puzzle);
+    match puzzle.save(Path::new(output)) {
+        Ok(()) => println!("Saved to {output}"),
+        Err(err) => println!("{err}"),
+    }
+}
+
+/// Print everything wrong with the answers in a puzzle file, and exit with 1 if
+/// anything is
+fn check_solution(args: &CheckArgs) {
+    let puzzle = Puzzle::load(Path::new(&args.puzzle))
+        .unwrap_or_else(|err| usage_error(format!("Unable to load {}: {err}", args.puzzle)));
+    if puzzle.answers.is_empty() {
+        usage_error(format!("{} has no answers to check", args.puzzle));
+    }
+    if puzzle.width * puzzle.height > usize::BITS as usize {
+        usage_error(format!("{} is too big a board", args.puzzle));
+    }
+    let placements: Vec<(String, Vec<usize>)> = puzzle
+        .answers
         .iter()
-        .flat_map(|start_point| start_point.iter().map(|(word, _)| word.clone()))
+        .map(|answer| (answer.word.clone(), answer.spots(puzzle.width)))
         .collect();
+    let violations = verify_solution(&puzzle.board(), &placements);
+    if violations.is_empty() {
+        println!("The solution is valid");
+        return;
+    }
+    println!("Found {} problems:", violations.len());
+    for violation in &violations {
+        println!("- {violation}");
+    }
+    std::process::exit(1);
+}
 
-    // Assume that these two are the same length
-    assert_eq!(condensed_words.len(), flattened_words_that_fit.len());
-
-    // Solver
-    let mut selected_blocks: SmallVec<[usize; 12]> = smallvec![];
-    let inds = inner_solve(
-        0usize,
-        &condensed_words,
-        &mut selected_blocks,
-        max_len,
-        board_w,
-        board_h,
-    )
-    .expect("Could not find a solution");
-
-    // Get the words from the indices
-    inds.iter()
-        .filter_map(|ind| condensed_words.iter().position(|x| x == ind))
-        .map(|ind| flattened_words_that_fit[ind].clone())
-        .collect()
+/// How one puzzle went in a batch
+#[derive(Serialize, Debug)]
+struct BatchRow {
+    puzzle: String,
+    status: &'static str,
+    #[serde(flatten)]
+    accuracy: Accuracy,
+    precision: f64,
+    recall: f64,
+    seconds: f64,
+    nodes: u64,
 }
 
-fn inner_solve(
-    board: usize,
-    blocks: &[usize],
-    selected_blocks: &mut SmallVec<[usize; 12]>,
-    max_len: usize,
-    board_w: usize,
-    board_h: usize,
-) -> Option<SmallVec<[usize; 12]>> {
-    // If we already have too many blocks, skips
-    if selected_blocks.len() >= max_len {
-        return None;
+/// How a whole batch went. Precision and recall count every word in the batch, and
+/// puzzles that weren't solved count as finding no words.
+#[derive(Serialize, Debug)]
+struct BatchTotals {
+    puzzles: usize,
+    solved: usize,
+    found: usize,
+    correct: usize,
+    answers: usize,
+    precision: f64,
+    recall: f64,
+    /// The share of puzzles with a marked spangram whose spangram was found
+    spangram_hit_rate: Option<f64>,
+    seconds: f64,
+    nodes: u64,
+}
+
+#[derive(Serialize, Debug)]
+struct BatchReport {
+    puzzles: Vec<BatchRow>,
+    total: BatchTotals,
+}
+
+/// A short name for how a solve ended, for reports
+fn status(failure: Option<&SolveError>) -> &'static str {
+    match failure {
+        None => "solved",
+        Some(SolveError::NoSolution) => "no solution",
+        Some(SolveError::Timeout) => "timeout",
+        Some(SolveError::Cancelled) => "cancelled",
+        Some(SolveError::UncoverableCell(_)) => "uncoverable",
+        Some(SolveError::TooManyWords { .. }) => "too many words",
+        Some(
+            SolveError::NotACandidate(_)
+            | SolveError::FoundConflict(_)
+            | SolveError::AmbiguousFound { .. },
+        ) => "bad found word",
     }
+}
 
-    for (idx, block) in blocks.iter().enumerate() {
-        // If this block can be placed
-        if !bit_overlaps(*block, board)
-            && no_diagonal_overlap(selected_blocks, *block, board_w, board_h)
-        {
-            // Place the block
-            let new_board = block | board;
-            selected_blocks.push(*block);
+/// A CSV field, quoted if it needs to be
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
 
-            // If we've filled the board
-            if new_board.count_ones() as usize == (board_h * board_w) {
-                return Some(selected_blocks.clone());
+/// Solve every puzzle in a directory at once, and report how close each came to
+/// the official answers
+fn run_batch(args: &BatchArgs) {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .expect("Unable to start the thread pool");
+    let memo_limit = memo_limit(args.memo_mb);
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&args.dir)
+        .expect("Unable to read directory")
+        .map(|entry| entry.expect("Unable to read directory").path())
+        .filter(|file| {
+            file.extension()
+                .is_some_and(|ext| ext == "json" || ext == "toml")
+        })
+        .collect();
+    files.sort();
+    let mut puzzles = vec![];
+    for file in files {
+        let name = file
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        match Puzzle::load(&file) {
+            Ok(puzzle) if (puzzle.width, puzzle.height) != (BOARD_W, BOARD_H) => {
+                eprintln!("Skipping {name}: the board isn't {BOARD_W}x{BOARD_H}")
             }
-
-            // If we're at max len, we haven't yet filled the board. Remove the block
-            // and skip to next word
-            if selected_blocks.len() >= max_len {
-                selected_blocks.pop();
-                continue;
+            Ok(puzzle) if puzzle.answers.is_empty() => {
+                eprintln!("Skipping {name}: it has no answers to compare to")
             }
+            Ok(puzzle) => puzzles.push((name, puzzle)),
+            Err(err) => eprintln!("Skipping {name}: {err}"),
+        }
+    }
 
-            // Try to add another block
-            if let Some(res) = inner_solve(
-                new_board,
-                &blocks[idx + 1..],
-                selected_blocks,
-                max_len,
-                board_w,
-                board_h,
-            ) {
-                return Some(res);
+    let words = fs::read_to_string(&args.dictionary_file).expect("Unable to read file");
+    let valid_words = dictionary_words(&words);
+    let control = SolveControl::default();
+    let cancel = control.cancel.clone();
+    ctrlc::set_handler(move || cancel.cancel()).expect("Unable to set the Ctrl-C handler");
+
+    let rows: Vec<BatchRow> = puzzles
+        .par_iter()
+        .map(|(name, puzzle)| {
+            let board = puzzle.board();
+            let words_that_fit = board.find_words_that_fit(&valid_words);
+            let control = SolveControl {
+                cancel: control.cancel.clone(),
+                deadline: args.timeout.and_then(deadline),
+                memo_limit,
+                spangram: puzzle.answers.iter().any(|answer| answer.spangram),
+                ..Default::default()
+            };
+            let max_words = puzzle.theme_words.unwrap_or(puzzle.answers.len());
+            let start = Instant::now();
+            let mut stats = SolveStats::default();
+            let result = solve(
+                &words_that_fit,
+                max_words,
+                BOARD_W,
+                BOARD_H,
+                &control,
+                &mut stats,
+            );
+            let seconds = start.elapsed().as_secs_f64();
+            eprintln!("{name}: {} in {seconds:.2}s", status(result.as_ref().err()));
+
+            let unsolved = Solution {
+                words: vec![],
+                paths: vec![],
+                spangram: None,
+            };
+            let accuracy = Accuracy::compare(result.as_ref().unwrap_or(&unsolved), &puzzle.answers);
+            BatchRow {
+                puzzle: name.clone(),
+                status: status(result.as_ref().err()),
+                accuracy,
+                precision: accuracy.precision(),
+                recall: accuracy.recall(),
+                seconds,
+                nodes: stats.nodes,
             }
+        })
+        .collect();
 
-            // Backtrack
-            selected_blocks.pop();
+    let mut accuracy = Accuracy::default();
+    for row in &rows {
+        accuracy += &row.accuracy;
+    }
+    let spangrams: Vec<bool> = rows
+        .iter()
+        .filter_map(|row| row.accuracy.spangram_hit)
+        .collect();
+    let total = BatchTotals {
+        puzzles: rows.len(),
+        solved: rows.iter().filter(|row| row.status == "solved").count(),
+        found: accuracy.found,
+        correct: accuracy.correct,
+        answers: accuracy.answers,
+        precision: accuracy.precision(),
+        recall: accuracy.recall(),
+        spangram_hit_rate: (!spangrams.is_empty())
+            .then(|| spangrams.iter().filter(|&&hit| hit).count() as f64 / spangrams.len() as f64),
+        seconds: rows.iter().map(|row| row.seconds).sum(),
+        nodes: rows.iter().map(|row| row.nodes).sum(),
+    };
+
+    match args.format {
+        ReportFormat::Json => {
+            let report = BatchReport {
+                puzzles: rows,
+                total,
+            };
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("Unable to write the report")
+            );
+        }
+        ReportFormat::Csv => {
+            println!(
+                "puzzle,status,found,correct,answers,precision,recall,spangram_hit,seconds,nodes"
+            );
+            let optional = |value: Option<String>| value.unwrap_or_default();
+            for row in &rows {
+                println!(
+                    "{},{},{},{},{},{:.3},{:.3},{},{:.3},{}",
+                    csv_field(&row.puzzle),
+                    row.status,
+                    row.accuracy.found,
+                    row.accuracy.correct,
+                    row.accuracy.answers,
+                    row.precision,
+                    row.recall,
+                    optional(row.accuracy.spangram_hit.map(|hit| hit.to_string())),
+                    row.seconds,
+                    row.nodes
+                );
+            }
+            // The total's spangram column is the hit rate
+            println!(
+                "total,{}/{} solved,{},{},{},{:.3},{:.3},{},{:.3},{}",
+                total.solved,
+                total.puzzles,
+                total.found,
+                total.correct,
+                total.answers,
+                total.precision,
+                total.recall,
+                optional(total.spangram_hit_rate.map(|rate| format!("{rate:.3}"))),
+                total.seconds,
+                total.nodes
+            );
         }
     }
-    None
 }
 
-fn main() {
-    let args = Args::parse();
+/// A word of a solution, as printed by `--format json` and `jsonl`
+#[derive(Serialize, Debug)]
+struct WordOutput {
+    word: String,
+    /// The spots the word runs through, as `[row, column]`
+    path: Vec<[usize; 2]>,
+    spangram: bool,
+}
 
-    let board = Board::parse_flat_board(&args.letters, 6, 8);
+/// A solution, as printed by `--format json` and `jsonl`
+#[derive(Serialize, Debug)]
+struct SolutionOutput {
+    words: Vec<WordOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<f64>,
+}
 
-    let words = fs::read_to_string(args.dictionary_file).expect("Unable to read file");
-    let mut valid_words: Vec<&str> = words
-        .lines()
-        .filter(|s| !s.contains(char::is_uppercase))
-        .filter(|&w| !w.ends_with("'s"))
-        .filter(|&w| w.len() >= 4)
-        .collect();
-    valid_words.sort_unstable();
-    valid_words.dedup();
+impl SolutionOutput {
+    fn new(board: &Board, solution: &Solution, score: Option<f64>) -> Self {
+        let words = solution
+            .words
+            .iter()
+            .zip(&solution.paths)
+            .enumerate()
+            .map(|(i, (word, path))| WordOutput {
+                word: word.clone(),
+                path: path
+                    .iter()
+                    .map(|&idx| [idx / board.w, idx % board.w])
+                    .collect(),
+                spangram: solution.spangram == Some(i),
+            })
+            .collect();
+        SolutionOutput { words, score }
+    }
+}
 
-    let filter_start = std::time::Instant::now();
-    let all_words_that_fit: Vec<Vec<(String, Vec<usize>)>> = (0..(6 * 8))
-        .map(|start_point| board.find_valid_words_from_start(start_point, &valid_words))
+/// A solution on one line of JSON, for `--format jsonl`
+fn solution_json(board: &Board, solution: &Solution, score: Option<f64>) -> String {
+    serde_json::to_string(&SolutionOutput::new(board, solution, score))
+        .expect("Unable to write the output")
+}
+
+/// Everything `--format json` prints
+#[derive(Serialize, Debug)]
+struct SolveOutput {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// How many solutions there are, with --count
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<u64>,
+    solutions: Vec<SolutionOutput>,
+    seconds: f64,
+    stats: SolveStats,
+}
+
+/// Find and print the sets of words covering the board
+fn solve_board(board_args: &BoardArgs, args: &SolveArgs) {
+    MESSAGES_TO_STDERR.store(args.format != OutputFormat::Text, Ordering::Relaxed);
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .expect("Unable to start the thread pool");
+    let memo_limit = memo_limit(args.memo_mb);
+
+    let (board, puzzle) = load_board(board_args);
+    let max_words = args
+        .max_words
+        .or(puzzle.as_ref().and_then(|puzzle| puzzle.theme_words))
+        .unwrap_or_else(|| {
+            usage_error("Give the maximum number of words, or theme_words in the puzzle".into())
+        });
+
+    let words = fs::read_to_string(&board_args.dictionary_file).expect("Unable to read file");
+    let valid_words = dictionary_words(&words);
+    let mut all_words_that_fit = find_words_that_fit(&board, &valid_words);
+
+    let excluded_words: Vec<String> = args
+        .exclude
+        .iter()
+        .map(|word| word.to_lowercase())
         .collect();
-    let filter_time = filter_start.elapsed().as_millis();
-    println!("Filtering words for all spots took {filter_time}ms");
-    println!(
-        "Found {} possible words",
-        all_words_that_fit.iter().flatten().count()
-    );
+    let excluded_paths: Vec<Vec<usize>> = args
+        .exclude_path
+        .iter()
+        .map(|cells| parse_path(cells, '-', &board))
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|err| usage_error(err));
+    let candidates = || all_words_that_fit.iter().flatten();
+    for word in &excluded_words {
+        if !candidates().any(|(candidate, _)| candidate == word) {
+            message!("{word} isn't a candidate, so excluding it changes nothing");
+        }
+    }
+    for (cells, path) in args.exclude_path.iter().zip(&excluded_paths) {
+        if !candidates().any(|(_, candidate)| candidate == path) {
+            message!("No candidate runs along {cells}, so excluding it changes nothing");
+        }
+    }
+    let excluded = exclude_placements(&mut all_words_that_fit, &excluded_words, &excluded_paths);
+    if excluded > 0 {
+        message!("Excluded {excluded} placements");
+    }
+
+    if let Some(file) = &args.category {
+        let name = Path::new(file)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let category = Category::parse(
+            &name,
+            &fs::read_to_string(file).expect("Unable to read file"),
+        );
+        let removed = category.restrict(&mut all_words_that_fit, BOARD_W, BOARD_H);
+        message!("Removed {removed} placements outside the {name} category");
+    }
+
+    // Hints leave only the words that fill their spots exactly
+    for (hint, spangram_hint) in args
+        .hint
+        .iter()
+        .map(|hint| (hint, false))
+        .chain(args.spangram_hint.iter().map(|hint| (hint, true)))
+    {
+        let cells = parse_path(hint, ',', &board).unwrap_or_else(|err| usage_error(err));
+        let block = cells.iter().fold(0, |acc, &idx| acc | (1 << idx));
+        if spangram_hint && !is_spangram(block, BOARD_W, BOARD_H) {
+            usage_error(format!(
+                "The spangram hint {hint} doesn't touch two opposite sides"
+            ));
+        }
+        if apply_hint(&mut all_words_that_fit, &cells) == 0 {
+            message!("No word fits the hint {hint}");
+        }
+    }
+
+    let found: Vec<(String, Vec<usize>)> = args
+        .found
+        .iter()
+        .map(|found| {
+            let (word, path) = parse_found(found, &board).unwrap_or_else(|err| usage_error(err));
+            find_found_word(&all_words_that_fit, &word, path.as_deref()).map_err(|err| (found, err))
+        })
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|(found, err)| {
+            eprintln!("{}", found_error(&board, found, &err));
+            std::process::exit(exit_code(&err));
+        });
+
+    let clue_text = args
+        .clue
+        .clone()
+        .or_else(|| puzzle.as_ref().and_then(|puzzle| puzzle.clue.clone()));
+    let clue = args.embeddings.as_ref().map(|file| {
+        let clue = clue_text.as_ref().unwrap_or_else(|| {
+            usage_error("--embeddings needs a clue, from --clue or the puzzle".into())
+        });
+        load_clue(clue, file, &all_words_that_fit)
+    });
+    let mut scorers =
+        base_scorers(&args.score, clue.as_ref()).unwrap_or_else(|err| usage_error(err));
+    // Stop on Ctrl-C or once the timeout passes, including while waiting on the
+    // scoring command
+    let cancel = CancelToken::new();
+    let deadline = args.timeout.and_then(deadline);
+    let command = args.scorer_cmd.as_ref().map(|command| {
+        CommandScorer::spawn(command, BOARD_W)
+            .map(|scorer| Arc::new(scorer.stop_when(cancel.clone(), deadline)))
+            .unwrap_or_else(|err| usage_error(format!("Unable to run {command}: {err}")))
+    });
+    if let Some(command) = &command {
+        scorers.push((1.0, command.clone()));
+    }
+    if let Some(file) = &args.prior {
+        let text = fs::read_to_string(file).expect("Unable to read file");
+        let prior: Prior = serde_json::from_str(&text)
+            .unwrap_or_else(|err| usage_error(format!("{file} isn't a prior: {err}")));
+        if prior.board_w != BOARD_W {
+            usage_error(format!("{file} was trained on boards of another size"));
+        }
+        scorers.push((1.0, Arc::new(prior)));
+    }
+    let scorer = combine_scorers(scorers);
+
+    // Keep track of progress
+    let control = SolveControl {
+        cancel,
+        deadline,
+        memo_limit,
+        spangram: args.spangram || args.spangram_hint.is_some() || args.category.is_some(),
+        found,
+        scorer,
+        ..Default::default()
+    };
+    let cancel = control.cancel.clone();
+    ctrlc::set_handler(move || cancel.cancel()).expect("Unable to set the Ctrl-C handler");
 
     // Find the solution
     let solve_start_time = std::time::Instant::now();
-    let solution = solve(&all_words_that_fit, args.max_words, 6, 8);
-    println!("\n\nFound solution!");
-    println!("{solution:?}");
+    let mut stats = SolveStats::default();
+    let done = Arc::new(AtomicBool::new(false));
+    let reporter = {
+        let (progress, done) = (control.progress.clone(), done.clone());
+        std::thread::spawn(move || report_progress(&progress, BOARD_W * BOARD_H, &done))
+    };
+    let single = !(args.count || args.all);
+    let mut failure = None;
+    let mut count = None;
+    let mut found_none = false;
+    let mut solutions: Vec<(Option<f64>, Solution)> = vec![];
+    let score = |solution: &Solution| {
+        control
+            .scorer
+            .as_ref()
+            .map(|scorer| scorer.score_cover(solution))
+    };
+    if args.count {
+        let found = count_solutions(
+            &all_words_that_fit,
+            max_words,
+            BOARD_W,
+            BOARD_H,
+            &control,
+            &mut stats,
+        );
+        message!("\n\nFound {found} solutions");
+        found_none = found == 0;
+        count = Some(found);
+    } else if args.all && args.format == OutputFormat::Jsonl {
+        // Print each solution as soon as it is found, rather than all at the end
+        let found = solve_each(
+            &all_words_that_fit,
+            max_words,
+            BOARD_W,
+            BOARD_H,
+            &control,
+            &mut stats,
+            |solution| println!("{}", solution_json(&board, &solution, score(&solution))),
+        );
+        message!("\n\nFound {found} solutions!");
+        found_none = found == 0;
+    } else if args.all {
+        let found = solve_all(
+            &all_words_that_fit,
+            max_words,
+            BOARD_W,
+            BOARD_H,
+            &control,
+            &mut stats,
+        );
+        message!("\n\nFound {} solutions!", found.len());
+        found_none = found.is_empty();
+        // Ranking is a pass over every cover once the search has found them all.
+        // Without a scorer they keep the order they were found in.
+        solutions = found
+            .into_iter()
+            .map(|solution| (score(&solution), solution))
+            .collect();
+        solutions.sort_by(|(a, _), (b, _)| b.unwrap_or(0.0).total_cmp(&a.unwrap_or(0.0)));
+    } else {
+        match solve(
+            &all_words_that_fit,
+            max_words,
+            BOARD_W,
+            BOARD_H,
+            &control,
+            &mut stats,
+        ) {
+            Ok(solution) => {
+                message!("\n\nFound solution!");
+                solutions.push((score(&solution), solution));
+            }
+            Err(err) => failure = Some(err),
+        }
+    }
+    done.store(true, Ordering::Relaxed);
+    reporter.join().expect("Progress reporter panicked");
     let solve_time = solve_start_time.elapsed().as_secs_f64();
-    println!("Solve took {solve_time:0.2}s");
+
+    // Counting and listing solutions still print what they found when stopped early.
+    // Finding none at all fails the same way as not finding a single solution.
+    let failure = failure
+        .or_else(|| control.stop_reason())
+        .or_else(|| found_none.then_some(SolveError::NoSolution));
+    match args.format {
+        OutputFormat::Text => {
+            for (score, solution) in &solutions {
+                if let (Some(score), false) = (score, single) {
+                    print!("{score:.3} ");
+                }
+                print_solution(solution);
+                if let (Some(score), true) = (score, single) {
+                    println!("Score {score:.3}");
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let output = SolveOutput {
+                status: status(failure.as_ref()),
+                error: failure.as_ref().map(SolveError::to_string),
+                count,
+                solutions: solutions
+                    .iter()
+                    .map(|(score, solution)| SolutionOutput::new(&board, solution, *score))
+                    .collect(),
+                seconds: solve_time,
+                stats: stats.clone(),
+            };
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output).expect("Unable to write the output")
+            );
+        }
+        OutputFormat::Jsonl => {
+            for (score, solution) in &solutions {
+                println!("{}", solution_json(&board, solution, *score));
+            }
+            if let Some(count) = count {
+                println!("{}", serde_json::json!({ "count": count }));
+            }
+        }
+    }
+
+    if let (Some(file), true, [(_, solution)]) = (&args.save, single, &solutions[..]) {
+        let mut saved = puzzle.clone().unwrap_or_else(|| Puzzle::new(&board));
+        saved.clue = clue_text.clone();
+        saved.set_answers(solution);
+        match saved.save(Path::new(file)) {
+            Ok(()) => message!("Saved to {file}"),
+            Err(err) => message!("{err}"),
+        }
+    }
+    message!("Solve took {solve_time:0.2}s");
+    if let Some(err) = command.and_then(|command| command.error()) {
+        message!("The scoring command failed, so its scores were left out: {err}");
+    }
+    message!(
+        "Placed {} words, pruned {} branches with unfillable regions, split the board {} times",
+        stats.nodes,
+        stats.pruned,
+        stats.splits
+    );
+    if stats.memo_lookups > 0 {
+        message!(
+            "Remembered {} unsolvable positions, {} of {} lookups hit ({:.1}%)",
+            stats.memo_entries,
+            stats.memo_hits,
+            stats.memo_lookups,
+            100.0 * stats.memo_hits as f64 / stats.memo_lookups as f64
+        );
+    }
+
+    let Some(err) = failure else {
+        return;
+    };
+    match err {
+        SolveError::Timeout | SolveError::Cancelled => {
+            message!("\nSearch {err}. Results may be incomplete.");
+            message!(
+                "Best partial cover ({}/{} spots):",
+                control.progress.best_coverage(),
+                BOARD_W * BOARD_H
+            );
+            message!("{:?}", control.progress.best_cover());
+        }
+        SolveError::UncoverableCell(spot) => {
+            message!(
+                "\nNo solution: no word fits through row {}, column {}",
+                spot / BOARD_W,
+                spot % BOARD_W
+            );
+        }
+        _ => message!("\nNo solution: {err}"),
+    }
+    let stopped = matches!(err, SolveError::Timeout | SolveError::Cancelled);
+    if excluded > 0 && !stopped {
+        message!("This may be because of the excluded placements; solve without them to check");
+    }
+    std::process::exit(exit_code(&err));
 }
 
 #[cfg(test)]
@@ -321,199 +1500,143 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    #[test]
-    fn test_parse_flat_board() {
-        let letters = "olwish heucbl sykoda ecpeny sheyub ranngm ormora hscksh";
-        let want_letters: Vec<char> = letters.replace(' ', "").chars().collect();
-        let want = Board {
-            letters: want_letters,
-            w: 6,
-            h: 8,
-        };
-        let got = Board::parse_flat_board(letters, 6, 8);
-
-        assert_eq!(want, got);
+    /// Candidates on the board "tal rgo esn", with ergo given a second path
+    fn words_that_fit() -> Vec<Vec<(String, Vec<usize>)>> {
+        vec![
+            vec![("talon".to_string(), vec![0, 1, 2, 5, 8])],
+            vec![
+                ("ergo".to_string(), vec![6, 3, 4, 5]),
+                ("ergo".to_string(), vec![7, 3, 4, 5]),
+            ],
+        ]
     }
 
     #[rstest]
-    #[case(0, vec![1, 3, 4])]
-    #[case(1, vec![0, 2, 4, 3, 5])]
-    #[case(2, vec![1, 5, 4])]
-    #[case(3, vec![0, 4, 6, 1, 7])]
-    #[case(4, vec![1, 3, 5, 7, 0, 2, 6, 8])]
-    #[case(5, vec![2, 4, 8, 1, 7])]
-    #[case(6, vec![3, 7, 4])]
-    #[case(7, vec![4, 6, 8, 3, 5])]
-    #[case(8, vec![5, 7, 4])]
-    fn test_get_neighbors(#[case] idx: usize, #[case] want: Vec<usize>) {
-        let board = Board::parse_flat_board("abc def ghi", 3, 3);
-        let got = board.get_neighbors(idx);
+    #[case("Talon", Ok(("talon", vec![0, 1, 2, 5, 8])))]
+    #[case("talon@r0c0-r0c1-r0c2-r1c2-r2c2", Ok(("talon", vec![0, 1, 2, 5, 8])))]
+    #[case("ergo@r2c1-r1c0-r1c1-r1c2", Ok(("ergo", vec![7, 3, 4, 5])))]
+    #[case("talon@r0c0-r0c1", Err("talon isn't a candidate along r0c0-r0c1"))]
+    #[case("talon@r9c0", Err("r9c0 isn't a spot on the board"))]
+    #[case("zzz", Err("zzz doesn't fit anywhere on the board"))]
+    #[case(
+        "ergo",
+        Err("ergo fits 2 ways, pick one of:\n  ergo@r2c0-r1c0-r1c1-r1c2\n  ergo@r2c1-r1c0-r1c1-r1c2")
+    )]
+    fn test_parse_found(#[case] found: &str, #[case] want: Result<(&str, Vec<usize>), &str>) {
+        let board = Board::parse_flat_board("tal rgo esn", 3, 3);
+        let want = want
+            .map(|(word, path)| (word.to_string(), path))
+            .map_err(str::to_string);
+        let got = parse_found(found, &board).and_then(|(word, path)| {
+            find_found_word(&words_that_fit(), &word, path.as_deref())
+                .map_err(|err| found_error(&board, found, &err))
+        });
         assert_eq!(want, got);
     }
 
     #[rstest]
-    #[case(0, vec![("talon".to_string(), vec![0, 1, 2, 5, 8])])]
-    #[case(1, vec![("argon".to_string(), vec![1, 3, 4, 5, 8])])]
-    #[case(2, vec![("long".to_string(), vec![2, 5, 8, 4]), ("lose".to_string(), vec![2, 5, 7,6])])]
-    #[case(3, vec![("rage".to_string(), vec![3, 1, 4, 6])])]
-    #[case(4, vec![("glare".to_string(), vec![4, 2, 1, 3, 6])])]
-    #[case(5, vec![("ogre".to_string(), vec![5, 4, 3, 6])])]
-    #[case(6, vec![("ergo".to_string(), vec![6, 3, 4, 5])])]
-    #[case(7, vec![("solar".to_string(), vec![7, 5, 2, 1, 3])])]
-    #[case(8, vec![("nose".to_string(), vec![8, 5, 7, 6])])]
-    fn test_find_valid_words_from_start(
-        #[case] start_point: usize,
-        #[case] want: Vec<(String, Vec<usize>)>,
+    #[case("r0c0-r1c1-r2c2", '-', Ok(vec![0, 4, 8]))]
+    #[case("r0c2, r2c0", ',', Ok(vec![2, 6]))]
+    #[case("r0c0-r0c3", '-', Err("r0c3 isn't a spot on the board"))]
+    #[case("r0c0,r1c1", '-', Err("r0c0,r1c1 isn't a spot on the board"))]
+    fn test_parse_path(
+        #[case] cells: &str,
+        #[case] separator: char,
+        #[case] want: Result<Vec<usize>, &str>,
     ) {
         let board = Board::parse_flat_board("tal rgo esn", 3, 3);
-
-        let words = vec![
-            "talon", "ogre", "sunny", "batch", "solar", "argon", "ergo", "lose", "long", "rage",
-            "tart", "nose", "glare",
-        ];
-
-        let mut got = board.find_valid_words_from_start(start_point, &words);
-        got.sort_unstable_by(|a, b| a.0.cmp(&b.0));
-
-        assert_eq!(want, got);
+        assert_eq!(
+            want.map_err(str::to_string),
+            parse_path(cells, separator, &board)
+        );
     }
 
-    #[test]
-    fn test_solve() {
-        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = vec![
-            vec![("talon".to_string(), vec![0, 1, 2, 5, 8])],
-            vec![("argon".to_string(), vec![1, 3, 4, 5, 8])],
-            vec![
-                ("long".to_string(), vec![2, 5, 8, 4]),
-                ("lose".to_string(), vec![2, 5, 7, 6]),
-            ],
-            vec![
-                ("rage".to_string(), vec![3, 1, 4, 6]),
-                ("regs".to_string(), vec![3, 6, 4, 7]),
-            ],
-            vec![("glare".to_string(), vec![4, 2, 1, 3, 6])],
-            vec![("ogre".to_string(), vec![5, 4, 3, 6])],
-            vec![("ergo".to_string(), vec![6, 3, 4, 5])],
-            vec![("solar".to_string(), vec![7, 5, 2, 1, 3])],
-            vec![("nose".to_string(), vec![8, 5, 7, 6])],
-        ];
-
-        let want: Vec<String> = vec!["talon".to_string(), "regs".to_string()];
-
-        let got = solve(&words_that_fit, 2, 3, 3);
-
-        assert_eq!(want, got);
+    #[rstest]
+    #[case("2.5", Ok(Duration::from_millis(2500)))]
+    #[case("0", Ok(Duration::ZERO))]
+    #[case("-1", Err("can't wait -1 seconds"))]
+    #[case("nan", Err("can't wait NaN seconds"))]
+    #[case("1e30", Err("can't wait 1000000000000000000000000000000 seconds"))]
+    #[case("soon", Err("soon isn't a number of seconds"))]
+    fn test_parse_seconds(#[case] secs: &str, #[case] want: Result<Duration, &str>) {
+        assert_eq!(want.map_err(str::to_string), parse_seconds(secs));
     }
 
-    #[test]
-    fn test_solve_2() {
-        let board = Board::parse_flat_board("tim lta ecl", 3, 3);
-
-        let words =
-            fs::read_to_string("american_english_dictionary.txt").expect("Unable to read file");
-        let mut valid_words: Vec<&str> = words
-            .lines()
-            .filter(|s| !s.contains(char::is_uppercase))
-            .filter(|&w| !w.ends_with("'s"))
-            .filter(|&w| w.len() >= 4)
-            .collect();
-        valid_words.sort_unstable();
-        valid_words.dedup();
-
-        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = (0..(3 * 3))
-            .map(|start_point| board.find_valid_words_from_start(start_point, &valid_words))
-            .collect();
-
-        let mut want: Vec<String> = "title clam"
-            .split_ascii_whitespace()
-            .map(std::string::ToString::to_string)
-            .collect();
-        want.sort_unstable();
-
-        let mut got = solve(&words_that_fit, 2, 3, 3);
-        got.sort_unstable();
+    #[rstest]
+    #[case("shape=2", Ok(2.0))]
+    #[case("shape=0.5", Ok(0.5))]
+    #[case("shape", Err("shape should look like KIND=WEIGHT"))]
+    #[case("shape=lots", Err("lots isn't a weight"))]
+    #[case(
+        "frequency=1",
+        Err("frequency scores need a file, like frequency:FILE=1")
+    )]
+    #[case("clue=1", Err("Scoring by clue needs --clue"))]
+    #[case("color=1", Err("color=1 isn't a kind of score"))]
+    fn test_parse_score(#[case] spec: &str, #[case] want: Result<f64, &str>) {
+        let got = parse_score(spec, None).map(|(weight, _)| weight);
+        assert_eq!(want.map_err(str::to_string), got);
+    }
 
-        assert_eq!(want, got);
+    /// Embeddings where "dance" is the clue and "house" is unrelated to it
+    fn dance_clue() -> (Embeddings, Vec<f32>) {
+        let embeddings = Embeddings::parse("dance 1 0\nhouse 0 1\n", |_| true);
+        let clue = embeddings.text_vector("dance").unwrap();
+        (embeddings, clue)
     }
 
     #[test]
-    #[should_panic]
-    fn test_solve_diag() {
-        // This test covers the case where two words exist that cross on the diagonal,
-        // which is not allowed
-        // In this case, if diagonals were allowed, it would find "camp" and "dress"
-        // but they are not, and it should panic
-        let board = Board::parse_flat_board("cdp amr sse", 3, 3);
-
-        let words =
-            fs::read_to_string("american_english_dictionary.txt").expect("Unable to read file");
-        let mut valid_words: Vec<&str> = words
-            .lines()
-            .filter(|s| !s.contains(char::is_uppercase))
-            .filter(|&w| !w.ends_with("'s"))
-            .filter(|&w| w.len() >= 4)
-            .collect();
-        valid_words.sort_unstable();
-        valid_words.dedup();
-
-        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = (0..(3 * 3))
-            .map(|start_point| board.find_valid_words_from_start(start_point, &valid_words))
-            .collect();
-
-        let got = solve(&words_that_fit, 2, 3, 3);
-        dbg!(got);
+    fn test_clue_scored_with_scorer_cmd() {
+        let clue = dance_clue();
+        let mut scorers = base_scorers(&[], Some(&clue)).unwrap();
+        let command = CommandScorer::spawn("while read -r line; do echo '{\"score\": 2}'; done", 4);
+        scorers.push((1.0, Arc::new(command.unwrap())));
+        let scorer = combine_scorers(scorers).unwrap();
+        assert_eq!(3.0, scorer.score_placement("dance", &[0, 1]));
+        assert_eq!(2.0, scorer.score_placement("house", &[0, 1]));
+
+        // The clue only counts through --score once that is given
+        let scorers = base_scorers(&["shape=1".to_string()], Some(&clue)).unwrap();
+        let scorer = combine_scorers(scorers).unwrap();
+        assert_eq!(1.0, scorer.score_placement("house", &[0, 1]));
     }
 
     #[test]
-    fn test_solve_long() {
-        let board = Board::parse_flat_board(
-            "hgueds uovaos lsnltw etcfme eoreor tuivkm tpekoo eslawn",
-            6,
-            8,
-        );
-
-        let words =
-            fs::read_to_string("american_english_dictionary.txt").expect("Unable to read file");
-        let mut valid_words: Vec<&str> = words
-            .lines()
-            .filter(|s| !s.contains(char::is_uppercase))
-            .filter(|&w| !w.ends_with("'s"))
-            .filter(|&w| w.len() >= 4)
-            .collect();
-        valid_words.sort_unstable();
-        valid_words.dedup();
-
-        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = (0..(6 * 8))
-            .map(|start_point| board.find_valid_words_from_start(start_point, &valid_words))
-            .collect();
-
-        let mut want: Vec<String> = "dance floss hustle moonwalk movie pets route twerk vogue"
-            .split_ascii_whitespace()
-            .map(std::string::ToString::to_string)
-            .collect();
-        want.sort_unstable();
-
-        let mut got = solve(&words_that_fit, 9, 6, 8);
-        got.sort_unstable();
+    fn test_clue_scored_with_prior() {
+        let words_that_fit = vec![
+            vec![("dance".to_string(), vec![0, 1])],
+            vec![("house".to_string(), vec![2, 3])],
+        ];
+        let mut prior = Prior::new(4);
+        prior.add_puzzle(&words_that_fit, &["dance".to_string()]);
+        let want = 1.0 + prior.likelihood("dance", &[0, 1]);
+
+        let mut scorers = base_scorers(&[], Some(&dance_clue())).unwrap();
+        scorers.push((1.0, Arc::new(prior)));
+        let scorer = combine_scorers(scorers).unwrap();
+        assert_eq!(want, scorer.score_placement("dance", &[0, 1]));
+    }
 
-        assert_eq!(want, got);
+    #[rstest]
+    #[case(SolveError::NoSolution, 3)]
+    #[case(SolveError::UncoverableCell(4), 4)]
+    #[case(SolveError::TooManyWords { min_words: 3, max_words: 2 }, 5)]
+    #[case(SolveError::Timeout, 6)]
+    #[case(SolveError::Cancelled, 7)]
+    #[case(SolveError::NotACandidate("talon".to_string()), 8)]
+    #[case(SolveError::FoundConflict("talon".to_string()), 8)]
+    #[case(SolveError::AmbiguousFound { word: "ergo".to_string(), paths: vec![] }, 8)]
+    fn test_exit_code(#[case] err: SolveError, #[case] want: i32) {
+        assert_eq!(want, exit_code(&err));
     }
 
     #[rstest]
-    #[case(0b0000, 0b0000, false)] // both empty
-    #[case(0b0001, 0b0010, false)] // ones in different places
-    #[case(0b0010, 0b0010, true)] // direct overlap
-    #[case(0b1100, 0b0011, false)] // ones in different places
-    #[case(0b1100, 0b0100, true)] // one overlap
-    #[case(0b1010, 0b1001, true)] // one overlap
-    #[case(0b1111, 0b0000, false)] // all of one or the other
-    #[case(0b1111, 0b1111, true)] // all ones all the way
-    fn test_bit_overlaps(
-        #[case] existing: usize,
-        #[case] new_indices: usize,
-        #[case] expected: bool,
-    ) {
-        let result = bit_overlaps(existing, new_indices);
-        assert_eq!(result, expected);
+    #[case("tango", "tango")]
+    #[case("", "")]
+    #[case("tango, waltz", "\"tango, waltz\"")]
+    #[case("the \"hustle\"", "\"the \"\"hustle\"\"\"")]
+    #[case("two\nlines", "\"two\nlines\"")]
+    fn test_csv_field(#[case] field: &str, #[case] want: &str) {
+        assert_eq!(want, csv_field(field));
     }
 }
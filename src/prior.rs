@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::Scorer;
+
+/// How strongly a word's own record is trusted over what its length and shape
+/// suggest. A word seen this many times counts for as much as its features.
+const WORD_WEIGHT: f64 = 2.0;
+
+/// One past puzzle in an archive of official answers, read one per line as JSON
+/// like `{"letters": "abc def ghi", "answers": ["word", ...]}`. The answers include
+/// the spangram.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedPuzzle {
+    /// Each row of letters, separated by a space
+    pub letters: String,
+    pub answers: Vec<String>,
+}
+
+/// How often something turned up among the candidates, and how often it was an
+/// answer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counts {
+    pub candidates: u64,
+    pub answers: u64,
+}
+
+impl Counts {
+    fn add(&mut self, answer: bool) {
+        self.candidates += 1;
+        self.answers += u64::from(answer);
+    }
+
+    /// The share of candidates that were answers, smoothed so that rare features
+    /// don't score zero or one
+    fn rate(&self) -> f64 {
+        (self.answers as f64 + 1.0) / (self.candidates as f64 + 2.0)
+    }
+}
+
+/// How likely a placement is to be a theme word, learned from past puzzles. Each
+/// word's own record is blended with the record of placements of the same length
+/// and number of turns, so unseen words still get a sensible score.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Prior {
+    pub board_w: usize,
+    pub words: HashMap<String, Counts>,
+    pub lengths: HashMap<usize, Counts>,
+    pub turns: HashMap<usize, Counts>,
+}
+
+impl Prior {
+    /// An empty prior for boards `board_w` spots wide
+    pub fn new(board_w: usize) -> Self {
+        Prior {
+            board_w,
+            ..Default::default()
+        }
+    }
+
+    /// Learn from one past puzzle: every candidate placement from
+    /// [`crate::Board::find_valid_words_from_start`] counts, and the ones spelling an
+    /// answer count as answers
+    pub fn add_puzzle(&mut self, words_that_fit: &[Vec<(String, Vec<usize>)>], answers: &[String]) {
+        let answers: HashSet<&String> = answers.iter().collect();
+        for (word, path) in words_that_fit.iter().flatten() {
+            let answer = answers.contains(word);
+            self.words.entry(word.clone()).or_default().add(answer);
+            self.lengths.entry(path.len()).or_default().add(answer);
+            self.turns
+                .entry(turns(path, self.board_w))
+                .or_default()
+                .add(answer);
+        }
+    }
+
+    /// The chance a placement is a theme word, from 0 to 1
+    pub fn likelihood(&self, word: &str, path: &[usize]) -> f64 {
+        let rate = |counts: Option<&Counts>| counts.copied().unwrap_or_default().rate();
+        let features = (rate(self.lengths.get(&path.len()))
+            + rate(self.turns.get(&turns(path, self.board_w))))
+            / 2.0;
+        let word = self.words.get(word).copied().unwrap_or_default();
+        (word.answers as f64 + WORD_WEIGHT * features) / (word.candidates as f64 + WORD_WEIGHT)
+    }
+}
+
+impl Scorer for Prior {
+    fn score_placement(&self, word: &str, path: &[usize]) -> f64 {
+        self.likelihood(word, path)
+    }
+}
+
+/// How many times a path changes direction
+fn turns(path: &[usize], board_w: usize) -> usize {
+    let step = |from: usize, to: usize| {
+        (
+            (to / board_w) as isize - (from / board_w) as isize,
+            (to % board_w) as isize - (from % board_w) as isize,
+        )
+    };
+    let steps: Vec<(isize, isize)> = path.windows(2).map(|w| step(w[0], w[1])).collect();
+    steps.windows(2).filter(|w| w[0] != w[1]).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(&[0, 1, 2], 0)]
+    #[case(&[0, 1, 4], 1)]
+    #[case(&[0, 4, 5, 9], 2)] // down, across, down
+    #[case(&[0], 0)]
+    fn test_turns(#[case] path: &[usize], #[case] want: usize) {
+        assert_eq!(want, turns(path, 4));
+    }
+
+    #[test]
+    fn test_prior() {
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = vec![
+            vec![
+                ("tall".to_string(), vec![0, 1, 2, 3]),
+                ("tale".to_string(), vec![0, 1, 2, 6]),
+            ],
+            vec![("ale".to_string(), vec![1, 2, 6])],
+        ];
+        let mut prior = Prior::new(4);
+        prior.add_puzzle(&words_that_fit, &["tall".to_string()]);
+        prior.add_puzzle(&words_that_fit, &["tall".to_string()]);
+
+        assert_eq!(
+            Counts {
+                candidates: 2,
+                answers: 2
+            },
+            prior.words["tall"]
+        );
+        assert_eq!(
+            Counts {
+                candidates: 4,
+                answers: 2
+            },
+            prior.lengths[&4]
+        );
+        assert_eq!(
+            Counts {
+                candidates: 2,
+                answers: 2
+            },
+            prior.turns[&0]
+        );
+
+        // A past answer beats a word that never was, and a straight four letter
+        // word nobody has seen sits in between
+        let tall = prior.likelihood("tall", &[0, 1, 2, 3]);
+        let tale = prior.likelihood("tale", &[0, 1, 2, 6]);
+        let toll = prior.likelihood("toll", &[0, 1, 2, 3]);
+        assert!(tall > toll && toll > tale, "{tall} {toll} {tale}");
+
+        let saved = serde_json::to_string(&prior).unwrap();
+        assert_eq!(prior, serde_json::from_str(&saved).unwrap());
+    }
+}
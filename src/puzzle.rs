@@ -0,0 +1,295 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, Solution};
+
+/// A Strands puzzle, as kept in a JSON or TOML file. Only the size and letters are
+/// needed; the rest is filled in as it becomes known. In TOML:
+///
+/// ```toml
+/// width = 3
+/// height = 3
+/// letters = ["tal", "rgo", "esn"]
+/// clue = "Long words"
+/// theme_words = 2
+///
+/// [[answers]]
+/// word = "talon"
+/// path = [[0, 0], [0, 1], [0, 2], [1, 2], [2, 2]]
+/// spangram = true
+///
+/// [[answers]]
+/// word = "regs"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub width: usize,
+    pub height: usize,
+    /// Each row of letters, top to bottom
+    pub letters: Vec<String>,
+    /// The theme clue the game gives
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clue: Option<String>,
+    /// How many words solve the puzzle, counting the spangram
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme_words: Option<usize>,
+    /// The official answers, if known
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub answers: Vec<Answer>,
+}
+
+/// One of a puzzle's answers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Answer {
+    pub word: String,
+    /// The spots the word runs through, in order, as `[row, column]`. Empty if not
+    /// known.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<[usize; 2]>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub spangram: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Why a puzzle file couldn't be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleError {
+    /// The file couldn't be read or written
+    Io(String),
+    /// The file isn't valid JSON or TOML for a puzzle
+    Format(String),
+    /// The puzzle has no rows or no columns
+    Empty,
+    /// The rows of letters don't match the puzzle's width and height
+    WrongSize { width: usize, height: usize },
+    /// An answer's path runs off the board
+    OffBoard(String),
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleError::Io(err) => write!(f, "{err}"),
+            PuzzleError::Format(err) => write!(f, "not a puzzle: {err}"),
+            PuzzleError::Empty => write!(f, "the board needs at least one row and column"),
+            PuzzleError::WrongSize { width, height } => {
+                write!(f, "the letters should be {height} rows of {width}")
+            }
+            PuzzleError::OffBoard(word) => write!(f, "the path for {word} runs off the board"),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+impl Puzzle {
+    /// A puzzle with just a board, to fill in later
+    pub fn new(board: &Board) -> Self {
+        Puzzle {
+            width: board.w,
+            height: board.h,
+            letters: board
+                .letters
+                .chunks(board.w)
+                .map(|row| row.iter().collect())
+                .collect(),
+            clue: None,
+            theme_words: None,
+            answers: vec![],
+        }
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, PuzzleError> {
+        let puzzle: Puzzle =
+            serde_json::from_str(text).map_err(|err| PuzzleError::Format(err.to_string()))?;
+        puzzle.checked()
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, PuzzleError> {
+        let puzzle: Puzzle =
+            toml::from_str(text).map_err(|err| PuzzleError::Format(err.to_string()))?;
+        puzzle.checked()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("puzzles are always valid JSON")
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("puzzles are always valid TOML")
+    }
+
+    /// Read a puzzle file, as TOML if its name ends in `.toml` and JSON otherwise
+    pub fn load(path: &Path) -> Result<Self, PuzzleError> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| PuzzleError::Io(format!("unable to read {}: {err}", path.display())))?;
+        if is_toml(path) {
+            Self::from_toml(&text)
+        } else {
+            Self::from_json(&text)
+        }
+    }
+
+    /// Write a puzzle file, as TOML if its name ends in `.toml` and JSON otherwise
+    pub fn save(&self, path: &Path) -> Result<(), PuzzleError> {
+        let text = if is_toml(path) {
+            self.to_toml()
+        } else {
+            self.to_json() + "\n"
+        };
+        fs::write(path, text)
+            .map_err(|err| PuzzleError::Io(format!("unable to write {}: {err}", path.display())))
+    }
+
+    pub fn board(&self) -> Board {
+        Board {
+            letters: self.letters.iter().flat_map(|row| row.chars()).collect(),
+            w: self.width,
+            h: self.height,
+        }
+    }
+
+    /// Replace the answers with the words of a solution
+    pub fn set_answers(&mut self, solution: &Solution) {
+        self.answers = solution
+            .words
+            .iter()
+            .zip(&solution.paths)
+            .enumerate()
+            .map(|(i, (word, path))| Answer {
+                word: word.clone(),
+                path: path
+                    .iter()
+                    .map(|&idx| [idx / self.width, idx % self.width])
+                    .collect(),
+                spangram: solution.spangram == Some(i),
+            })
+            .collect();
+    }
+
+    /// Lowercase the letters, like the dictionary, and make sure they fill the
+    /// board and every path stays on it
+    fn checked(mut self) -> Result<Self, PuzzleError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(PuzzleError::Empty);
+        }
+        for row in &mut self.letters {
+            *row = row.to_lowercase();
+        }
+        let wrong_size = PuzzleError::WrongSize {
+            width: self.width,
+            height: self.height,
+        };
+        if self.letters.len() != self.height
+            || self
+                .letters
+                .iter()
+                .any(|row| row.chars().count() != self.width)
+        {
+            return Err(wrong_size);
+        }
+        for answer in &self.answers {
+            if answer
+                .path
+                .iter()
+                .any(|&[row, col]| row >= self.height || col >= self.width)
+            {
+                return Err(PuzzleError::OffBoard(answer.word.clone()));
+            }
+        }
+        Ok(self)
+    }
+}
+
+impl Answer {
+    /// The path as indices into the board's letters
+    pub fn spots(&self, width: usize) -> Vec<usize> {
+        self.path
+            .iter()
+            .map(|&[row, col]| row * width + col)
+            .collect()
+    }
+
+    /// Set the path from indices into the board's letters
+    pub fn set_spots(&mut self, spots: &[usize], width: usize) {
+        self.path = spots
+            .iter()
+            .map(|&idx| [idx / width, idx % width])
+            .collect();
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn talon_puzzle() -> Puzzle {
+        let mut puzzle = Puzzle::new(&Board::parse_flat_board("tal rgo esn", 3, 3));
+        puzzle.clue = Some("Long words".to_string());
+        puzzle.theme_words = Some(2);
+        puzzle.set_answers(&Solution {
+            words: vec!["talon".to_string(), "regs".to_string()],
+            paths: vec![vec![0, 1, 2, 5, 8], vec![]],
+            spangram: Some(0),
+        });
+        puzzle
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let puzzle = talon_puzzle();
+        assert_eq!(vec!["tal", "rgo", "esn"], puzzle.letters);
+        assert_eq!([1, 2], puzzle.answers[0].path[3]);
+        assert_eq!(vec![0, 1, 2, 5, 8], puzzle.answers[0].spots(3));
+        let mut answer = puzzle.answers[1].clone();
+        answer.set_spots(&[3, 6, 4, 7], 3);
+        assert_eq!(vec![3, 6, 4, 7], answer.spots(3));
+        assert_eq!(puzzle.board(), Board::parse_flat_board("tal rgo esn", 3, 3));
+
+        assert_eq!(Ok(puzzle.clone()), Puzzle::from_json(&puzzle.to_json()));
+        assert_eq!(Ok(puzzle.clone()), Puzzle::from_toml(&puzzle.to_toml()));
+    }
+
+    #[test]
+    fn test_minimal_toml() {
+        let text = "width = 3\nheight = 1\nletters = [\"abc\"]\n\n[[answers]]\nword = \"abc\"\n";
+        let puzzle = Puzzle::from_toml(text).unwrap();
+        assert_eq!(None, puzzle.clue);
+        assert!(puzzle.answers[0].path.is_empty());
+        assert!(!puzzle.answers[0].spangram);
+    }
+
+    #[test]
+    fn test_letters_lowercased() {
+        let puzzle = Puzzle::from_json(r#"{"width": 3, "height": 1, "letters": ["TaL"]}"#);
+        assert_eq!(vec!["tal"], puzzle.unwrap().letters);
+    }
+
+    #[rstest]
+    #[case(r#"{"width": 0, "height": 0, "letters": []}"#, PuzzleError::Empty)]
+    #[case(
+        r#"{"width": 0, "height": 2, "letters": ["", ""]}"#,
+        PuzzleError::Empty
+    )]
+    #[case(r#"{"width": 3, "height": 0, "letters": []}"#, PuzzleError::Empty)]
+    #[case(r#"{"width": 3, "height": 2, "letters": ["abc"]}"#, PuzzleError::WrongSize { width: 3, height: 2 })]
+    #[case(r#"{"width": 3, "height": 1, "letters": ["ab"]}"#, PuzzleError::WrongSize { width: 3, height: 1 })]
+    #[case(
+        r#"{"width": 3, "height": 1, "letters": ["abc"], "answers": [{"word": "ab", "path": [[0, 1], [1, 1]]}]}"#,
+        PuzzleError::OffBoard("ab".to_string())
+    )]
+    fn test_invalid(#[case] text: &str, #[case] want: PuzzleError) {
+        assert_eq!(Err(want), Puzzle::from_json(text));
+    }
+}
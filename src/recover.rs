@@ -0,0 +1,171 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::words::squash;
+use crate::{is_spangram, solve_all, Board, Solution, SolveControl, SolveStats};
+
+/// Why the paths of a puzzle's answers couldn't be found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoverError {
+    /// The answer isn't spelled along any path on the board
+    NotOnBoard(String),
+    /// Each answer fits somewhere, but they can't all fit at once without
+    /// overlapping or crossing
+    NoCover,
+}
+
+impl fmt::Display for RecoverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecoverError::NotOnBoard(word) => write!(f, "{word} doesn't fit anywhere on the board"),
+            RecoverError::NoCover => write!(f, "the answers can't cover the board together"),
+        }
+    }
+}
+
+impl std::error::Error for RecoverError {}
+
+/// Find every way of placing each answer exactly once so that together they cover
+/// the board without overlapping or crossing. Each solution has the answers in the
+/// order given, with their paths. If `spangram` is the index of the answer known to
+/// be the spangram, only placements of it touching two opposite sides count. More
+/// than one solution means the words alone don't pin down the paths.
+pub fn recover_paths(
+    board: &Board,
+    answers: &[String],
+    spangram: Option<usize>,
+) -> Result<Vec<Solution>, RecoverError> {
+    let normalized: Vec<String> = answers
+        .iter()
+        .map(|answer| squash(&answer.to_lowercase()))
+        .collect();
+    let mut words: Vec<&str> = normalized.iter().map(String::as_str).collect();
+    words.sort_unstable();
+    words.dedup();
+    let words_that_fit = board.find_words_that_fit(&words);
+
+    let on_board: HashSet<&String> = words_that_fit
+        .iter()
+        .flatten()
+        .map(|(word, _)| word)
+        .collect();
+    if let Some(i) = (0..answers.len()).find(|&i| !on_board.contains(&normalized[i])) {
+        return Err(RecoverError::NotOnBoard(answers[i].clone()));
+    }
+
+    let covers = solve_all(
+        &words_that_fit,
+        answers.len(),
+        board.w,
+        board.h,
+        &SolveControl::default(),
+        &mut SolveStats::default(),
+    );
+    let mut solutions = vec![];
+    let mut seen = HashSet::new();
+    for cover in covers {
+        // Hand each of the cover's words to the first answer spelling it that is
+        // still without a path
+        let mut unplaced: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, word) in normalized.iter().enumerate().rev() {
+            unplaced.entry(word).or_default().push(i);
+        }
+        let mut paths = vec![vec![]; answers.len()];
+        let mut all_placed = cover.words.len() == answers.len();
+        for (word, path) in cover.words.iter().zip(&cover.paths) {
+            match unplaced.get_mut(word.as_str()).and_then(Vec::pop) {
+                Some(i) => paths[i] = path.clone(),
+                None => all_placed = false,
+            }
+        }
+        if !all_placed {
+            continue;
+        }
+        if let Some(i) = spangram {
+            let block = paths[i].iter().fold(0, |acc, &idx| acc | (1 << idx));
+            if !is_spangram(block, board.w, board.h) {
+                continue;
+            }
+        }
+        // Covers that only swap the paths of a repeated answer are the same
+        if seen.insert(sorted_paths(&normalized, &paths)) {
+            solutions.push(Solution {
+                words: answers.to_vec(),
+                paths,
+                spangram,
+            });
+        }
+    }
+    if solutions.is_empty() {
+        return Err(RecoverError::NoCover);
+    }
+    Ok(solutions)
+}
+
+/// The indices of the answers whose path differs between solutions from
+/// [`recover_paths`]
+pub fn ambiguous_answers(solutions: &[Solution]) -> Vec<usize> {
+    let Some(first) = solutions.first() else {
+        return vec![];
+    };
+    (0..first.paths.len())
+        .filter(|&i| {
+            solutions
+                .iter()
+                .any(|solution| solution.paths[i] != first.paths[i])
+        })
+        .collect()
+}
+
+/// The placements of a cover with the answers' order forgotten
+fn sorted_paths<'a>(words: &'a [String], paths: &[Vec<usize>]) -> Vec<(&'a String, Vec<usize>)> {
+    let mut placements: Vec<(&String, Vec<usize>)> =
+        words.iter().zip(paths.iter().cloned()).collect();
+    placements.sort();
+    placements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn answers(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_recover_paths() {
+        let board = Board::parse_flat_board("tal rgo esn", 3, 3);
+        let solutions = recover_paths(&board, &answers(&["REGS", "talon"]), Some(1)).unwrap();
+        assert_eq!(1, solutions.len());
+        assert_eq!(
+            vec![vec![3, 6, 4, 7], vec![0, 1, 2, 5, 8]],
+            solutions[0].paths
+        );
+        assert_eq!(answers(&["REGS", "talon"]), solutions[0].words);
+        assert!(ambiguous_answers(&solutions).is_empty());
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        // Each "ab" can run across or down
+        let board = Board::parse_flat_board("ab ba", 2, 2);
+        let solutions = recover_paths(&board, &answers(&["ab", "ab"]), None).unwrap();
+        assert_eq!(2, solutions.len());
+        assert_eq!(vec![0, 1], ambiguous_answers(&solutions));
+    }
+
+    #[rstest]
+    #[case(&["talon", "regs"], Some(1), RecoverError::NoCover)]
+    #[case(&["talon", "reg"], None, RecoverError::NoCover)]
+    #[case(&["talon", "zzz"], None, RecoverError::NotOnBoard("zzz".to_string()))]
+    fn test_recover_paths_fails(
+        #[case] words: &[&str],
+        #[case] spangram: Option<usize>,
+        #[case] want: RecoverError,
+    ) {
+        let board = Board::parse_flat_board("tal rgo esn", 3, 3);
+        assert_eq!(Err(want), recover_paths(&board, &answers(words), spangram));
+    }
+}
@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{CancelToken, Category, Embeddings, Solution};
+
+/// A way of judging how likely words are to be the puzzle's answers. Higher scores
+/// are better guesses.
+pub trait Scorer: fmt::Debug + Send + Sync {
+    /// How good a guess one word is, placed along `path`
+    fn score_placement(&self, word: &str, path: &[usize]) -> f64;
+
+    /// How good a guess a whole cover is. By default, the average of its words'
+    /// scores, so covers with more words aren't favored.
+    fn score_cover(&self, solution: &Solution) -> f64 {
+        if solution.words.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = solution
+            .words
+            .iter()
+            .zip(&solution.paths)
+            .map(|(word, path)| self.score_placement(word, path))
+            .sum();
+        total / solution.words.len() as f64
+    }
+}
+
+/// Favors common words. Scores run from 0 for words missing from the list to 1 for
+/// the most common, on a log scale.
+#[derive(Debug, Clone)]
+pub struct FrequencyScorer {
+    frequencies: HashMap<String, u64>,
+    log_max: f64,
+}
+
+impl FrequencyScorer {
+    pub fn new(frequencies: HashMap<String, u64>) -> Self {
+        let max = frequencies.values().copied().max().unwrap_or(0);
+        FrequencyScorer {
+            frequencies,
+            log_max: (max as f64).ln_1p(),
+        }
+    }
+}
+
+impl Scorer for FrequencyScorer {
+    fn score_placement(&self, word: &str, _path: &[usize]) -> f64 {
+        match self.frequencies.get(word) {
+            Some(&count) if self.log_max > 0.0 => (count as f64).ln_1p() / self.log_max,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Favors words related to the theme clue, and covers whose words relate to each
+/// other, as measured by [`Embeddings::coherence`]
+#[derive(Debug, Clone)]
+pub struct EmbeddingScorer {
+    embeddings: Embeddings,
+    clue: Vec<f32>,
+}
+
+impl EmbeddingScorer {
+    /// `clue` is the clue's vector, from [`Embeddings::text_vector`]
+    pub fn new(embeddings: Embeddings, clue: Vec<f32>) -> Self {
+        EmbeddingScorer { embeddings, clue }
+    }
+}
+
+impl Scorer for EmbeddingScorer {
+    fn score_placement(&self, word: &str, _path: &[usize]) -> f64 {
+        self.embeddings.similarity(word, &self.clue) as f64
+    }
+
+    fn score_cover(&self, solution: &Solution) -> f64 {
+        self.embeddings.coherence(&solution.words, &self.clue) as f64
+    }
+}
+
+/// Favors paths that run in straight lines: the share of turns along the path that
+/// keep going the same way. Words of two letters or fewer score 1.
+#[derive(Debug, Clone)]
+pub struct ShapeScorer {
+    board_w: usize,
+}
+
+impl ShapeScorer {
+    pub fn new(board_w: usize) -> Self {
+        ShapeScorer { board_w }
+    }
+
+    fn step(&self, from: usize, to: usize) -> (isize, isize) {
+        let row = |idx: usize| (idx / self.board_w) as isize;
+        let col = |idx: usize| (idx % self.board_w) as isize;
+        (row(to) - row(from), col(to) - col(from))
+    }
+}
+
+impl Scorer for ShapeScorer {
+    fn score_placement(&self, _word: &str, path: &[usize]) -> f64 {
+        if path.len() < 3 {
+            return 1.0;
+        }
+        let steps: Vec<(isize, isize)> = path.windows(2).map(|w| self.step(w[0], w[1])).collect();
+        let straight = steps.windows(2).filter(|w| w[0] == w[1]).count();
+        straight as f64 / (steps.len() - 1) as f64
+    }
+}
+
+/// Scores 1 for words in the category and 0 for the rest
+#[derive(Debug, Clone)]
+pub struct CategoryScorer(pub Category);
+
+impl Scorer for CategoryScorer {
+    fn score_placement(&self, word: &str, _path: &[usize]) -> f64 {
+        if self.0.words.contains(word) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Asks another program for scores, so models can be tried out without rebuilding
+/// the solver. The command is run with `sh -c` and sent one JSON request per line
+/// on stdin, with paths as `[row, column]` pairs:
+///
+/// ```text
+/// {"kind": "placement", "word": "dance", "path": [[0, 1], [1, 1], ...]}
+/// {"kind": "cover", "words": ["dance", ...], "paths": [[[0, 1], ...], ...]}
+/// ```
+///
+/// It answers each with a line like `{"score": 0.75}`. If the program fails,
+/// answers with anything else, or is still thinking when the solve is cancelled or
+/// runs out of time, every score from then on is zero and [`CommandScorer::error`]
+/// says what went wrong.
+pub struct CommandScorer {
+    process: Mutex<CommandProcess>,
+    board_w: usize,
+    /// Placements are scored once each, however often they are asked about
+    placements: Mutex<HashMap<(String, Vec<usize>), f64>>,
+    error: Mutex<Option<String>>,
+    /// Waiting for an answer stops once this is cancelled or the deadline passes
+    cancel: CancelToken,
+    deadline: Option<Instant>,
+}
+
+struct CommandProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    /// Each line the command prints, read on a thread of its own so that waiting
+    /// for one can be given up on
+    lines: Receiver<io::Result<String>>,
+    /// Set once an answer was given up on, so the command is killed rather than
+    /// waited for
+    abandoned: bool,
+}
+
+/// How often to check whether to stop waiting for the command to answer
+const ANSWER_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Deserialize)]
+struct CommandResponse {
+    score: f64,
+}
+
+impl CommandScorer {
+    /// Start `command`, which keeps running until the scorer is dropped
+    pub fn spawn(command: &str, board_w: usize) -> io::Result<Self> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            let read = match stdout.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => Ok(line),
+                Err(err) => Err(err),
+            };
+            if sender.send(read).is_err() {
+                break;
+            }
+        });
+        Ok(CommandScorer {
+            process: Mutex::new(CommandProcess {
+                child,
+                stdin,
+                lines,
+                abandoned: false,
+            }),
+            board_w,
+            placements: Mutex::default(),
+            error: Mutex::default(),
+            cancel: CancelToken::default(),
+            deadline: None,
+        })
+    }
+
+    /// Stop waiting for answers once `cancel` is cancelled or `deadline` passes,
+    /// which should be the same as the solve's [`crate::SolveControl`]
+    pub fn stop_when(mut self, cancel: CancelToken, deadline: Option<Instant>) -> Self {
+        self.cancel = cancel;
+        self.deadline = deadline;
+        self
+    }
+
+    /// What went wrong talking to the command, if anything did
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    fn coordinates(&self, path: &[usize]) -> Vec<[usize; 2]> {
+        path.iter()
+            .map(|&idx| [idx / self.board_w, idx % self.board_w])
+            .collect()
+    }
+
+    /// Why to stop waiting for an answer, if it is time to
+    fn stop_reason(&self) -> Option<&'static str> {
+        if self.cancel.is_cancelled() {
+            Some("the solve was cancelled")
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some("the solve ran out of time")
+        } else {
+            None
+        }
+    }
+
+    /// Send one request and read back its score, or zero once anything has failed
+    fn ask(&self, request: serde_json::Value) -> f64 {
+        let mut process = self.process.lock().unwrap();
+        // Another thread may have failed while this one waited for its turn
+        if self.error().is_some() {
+            return 0.0;
+        }
+        match process.ask(&request, || self.stop_reason()) {
+            Ok(score) => score,
+            Err(err) => {
+                *self.error.lock().unwrap() = Some(err);
+                0.0
+            }
+        }
+    }
+}
+
+impl CommandProcess {
+    fn ask(
+        &mut self,
+        request: &serde_json::Value,
+        stop_reason: impl Fn() -> Option<&'static str>,
+    ) -> Result<f64, String> {
+        let stdin = self.stdin.as_mut().ok_or("the command's input is closed")?;
+        writeln!(stdin, "{request}")
+            .and_then(|()| stdin.flush())
+            .map_err(|err| format!("couldn't send a request: {err}"))?;
+        let line = loop {
+            if let Some(reason) = stop_reason() {
+                self.abandoned = true;
+                return Err(format!("gave up waiting for an answer because {reason}"));
+            }
+            match self.lines.recv_timeout(ANSWER_POLL_INTERVAL) {
+                Ok(line) => break line,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err("the command stopped answering".to_string())
+                }
+            }
+        };
+        match line {
+            Ok(line) => serde_json::from_str::<CommandResponse>(&line)
+                .map(|response| response.score)
+                .map_err(|err| format!("couldn't read the answer {:?}: {err}", line.trim_end())),
+            Err(err) => Err(format!("couldn't read an answer: {err}")),
+        }
+    }
+}
+
+impl Drop for CommandProcess {
+    fn drop(&mut self) {
+        // Closing its input tells the command to finish, unless it is stuck on a
+        // request that was given up on
+        self.stdin.take();
+        if self.abandoned {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+impl fmt::Debug for CommandScorer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CommandScorer")
+            .field("board_w", &self.board_w)
+            .field("error", &self.error())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Scorer for CommandScorer {
+    fn score_placement(&self, word: &str, path: &[usize]) -> f64 {
+        let key = (word.to_string(), path.to_vec());
+        if let Some(&score) = self.placements.lock().unwrap().get(&key) {
+            return score;
+        }
+        let score = self.ask(json!({
+            "kind": "placement",
+            "word": word,
+            "path": self.coordinates(path),
+        }));
+        self.placements.lock().unwrap().insert(key, score);
+        score
+    }
+
+    fn score_cover(&self, solution: &Solution) -> f64 {
+        let paths: Vec<Vec<[usize; 2]>> = solution
+            .paths
+            .iter()
+            .map(|path| self.coordinates(path))
+            .collect();
+        self.ask(json!({
+            "kind": "cover",
+            "words": solution.words,
+            "paths": paths,
+        }))
+    }
+}
+
+/// Adds up other scorers, each multiplied by its weight
+#[derive(Debug, Clone, Default)]
+pub struct WeightedScorer {
+    scorers: Vec<(f64, Arc<dyn Scorer>)>,
+}
+
+impl WeightedScorer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `scorer`, counting `weight` times
+    pub fn with(mut self, weight: f64, scorer: Arc<dyn Scorer>) -> Self {
+        self.scorers.push((weight, scorer));
+        self
+    }
+}
+
+impl Scorer for WeightedScorer {
+    fn score_placement(&self, word: &str, path: &[usize]) -> f64 {
+        self.scorers
+            .iter()
+            .map(|(weight, scorer)| weight * scorer.score_placement(word, path))
+            .sum()
+    }
+
+    fn score_cover(&self, solution: &Solution) -> f64 {
+        self.scorers
+            .iter()
+            .map(|(weight, scorer)| weight * scorer.score_cover(solution))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{solve, SolveControl, SolveError, SolveStats};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(&[0, 1, 2, 3], 1.0)] // along the top row of a 4 wide board
+    #[case(&[0, 1, 5, 6], 0.0)] // zigzag
+    #[case(&[0, 5, 10, 11], 0.5)] // diagonal, then a turn
+    #[case(&[0, 1], 1.0)]
+    fn test_shape_scorer(#[case] path: &[usize], #[case] want: f64) {
+        assert_eq!(want, ShapeScorer::new(4).score_placement("", path));
+    }
+
+    #[test]
+    fn test_weighted_scorer() {
+        let frequencies = HashMap::from([("dance".to_string(), 99), ("vogue".to_string(), 9)]);
+        let category = Category::parse("dances", "vogue\n");
+        let scorer = WeightedScorer::new()
+            .with(1.0, Arc::new(FrequencyScorer::new(frequencies)))
+            .with(2.0, Arc::new(CategoryScorer(category)));
+
+        assert_eq!(1.0, scorer.score_placement("dance", &[]));
+        assert_eq!(2.5, scorer.score_placement("vogue", &[]));
+        assert_eq!(0.0, scorer.score_placement("house", &[]));
+        let solution = Solution {
+            words: vec!["dance".to_string(), "vogue".to_string()],
+            paths: vec![vec![], vec![]],
+            spangram: None,
+        };
+        assert_eq!(1.75, scorer.score_cover(&solution));
+    }
+
+    #[test]
+    fn test_command_scorer() {
+        let script = r#"while read -r line; do
+            case "$line" in
+                *'"cover"'*) echo '{"score": 3}' ;;
+                *'[[0,1],[1,1]]'*) echo '{"score": 1.5}' ;;
+                *) echo 'nonsense' ;;
+            esac
+        done"#;
+        let scorer = CommandScorer::spawn(script, 4).unwrap();
+        assert_eq!(1.5, scorer.score_placement("ab", &[1, 5]));
+        assert_eq!(1.5, scorer.score_placement("ab", &[1, 5]));
+        let solution = Solution {
+            words: vec!["ab".to_string()],
+            paths: vec![vec![1, 5]],
+            spangram: None,
+        };
+        assert_eq!(3.0, scorer.score_cover(&solution));
+        assert_eq!(None, scorer.error());
+
+        // A bad answer turns the scorer off
+        assert_eq!(0.0, scorer.score_placement("cd", &[2, 3]));
+        assert!(scorer.error().unwrap().contains("nonsense"));
+        assert_eq!(0.0, scorer.score_cover(&solution));
+    }
+
+    #[test]
+    fn test_command_scorer_exits() {
+        let scorer = CommandScorer::spawn("exit 0", 4).unwrap();
+        assert_eq!(0.0, scorer.score_placement("ab", &[1, 5]));
+        assert!(scorer.error().is_some());
+    }
+
+    #[test]
+    fn test_command_scorer_gives_up() {
+        // The command never answers, so the solve has to time out without its scores
+        let start = Instant::now();
+        let deadline = Some(start + Duration::from_millis(200));
+        let scorer = CommandScorer::spawn("sleep 100", 8)
+            .unwrap()
+            .stop_when(CancelToken::new(), deadline);
+        let scorer = Arc::new(scorer);
+        let control = SolveControl {
+            deadline,
+            scorer: Some(scorer.clone()),
+            ..Default::default()
+        };
+        // Too many words deep for the search to finish while splitting up its work
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = (0..8)
+            .map(|spot| vec![(format!("w{spot}"), vec![spot])])
+            .collect();
+        let got = solve(
+            &words_that_fit,
+            8,
+            8,
+            1,
+            &control,
+            &mut SolveStats::default(),
+        );
+        assert_eq!(Err(SolveError::Timeout), got);
+        assert!(scorer.error().unwrap().contains("ran out of time"));
+        drop(control);
+        drop(scorer);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
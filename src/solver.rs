@@ -0,0 +1,2030 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use smallvec::{smallvec, SmallVec};
+
+use crate::scoring::Scorer;
+use crate::{diagonals, is_spangram, square_corner_mask};
+
+/// Function to check if there is any overlap between the existing indices and new indices
+fn bit_overlaps(existing: usize, new_indices: usize) -> bool {
+    existing & new_indices != 0
+}
+
+/// Function to convert a &[usize] to a single usize representing the bits
+fn indices_to_bits(indices: &[usize]) -> usize {
+    indices.iter().fold(0, |acc, &idx| acc | (1 << idx))
+}
+
+/// A mask with one bit set for every spot on a `board_w` x `board_h` board
+fn full_board_mask(board_w: usize, board_h: usize) -> usize {
+    usize::MAX >> (usize::BITS as usize - board_w * board_h)
+}
+
+/// Grow `mask` by one spot in all eight directions, staying on the board
+fn dilate(mask: usize, board_w: usize, board_h: usize) -> usize {
+    let full = full_board_mask(board_w, board_h);
+    // Spots in the first column have no west neighbor, and vice versa for the last
+    let first_col = (0..board_h).fold(0, |acc, row| acc | (1 << (row * board_w)));
+    let last_col = first_col << (board_w - 1);
+
+    let horizontal = (mask | ((mask << 1) & !first_col) | ((mask >> 1) & !last_col)) & full;
+    (horizontal | (horizontal << board_w) | (horizontal >> board_w)) & full
+}
+
+/// Split the spots in `empty` into groups that are connected to each other, using
+/// the same eight-way adjacency that words are built with.
+fn empty_components(empty: usize, board_w: usize, board_h: usize) -> SmallVec<[usize; 4]> {
+    let mut components = smallvec![];
+    let mut remaining = empty;
+    while remaining != 0 {
+        // Flood fill outwards from the lowest remaining spot
+        let mut component = remaining & remaining.wrapping_neg();
+        loop {
+            let grown = dilate(component, board_w, board_h) & remaining;
+            if grown == component {
+                break;
+            }
+            component = grown;
+        }
+        components.push(component);
+        remaining &= !component;
+    }
+    components
+}
+
+/// Where the search stands on placing a spangram
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Spangram {
+    /// Spangrams are ordinary words
+    Ignored,
+    /// Exactly one more spangram has to be placed
+    Needed,
+    /// The spangram has been placed, or belongs somewhere else, so no more can be
+    Used,
+}
+
+/// Everything about the words placed so far that affects which words can go next,
+/// besides the spots they fill
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Placed {
+    /// The 2x2 squares where a placed word covers each diagonal, as from
+    /// [`diagonals`]. Whether a new word crosses anything depends only on these.
+    backslash: usize,
+    slash: usize,
+    spangram: Spangram,
+}
+
+impl Placed {
+    /// The same position, with a different rule for the spangram
+    fn with_spangram(&self, spangram: Spangram) -> Self {
+        Placed { spangram, ..*self }
+    }
+}
+
+/// A position in the search, as far as covering its empty spots is concerned. Only
+/// the diagonals that a word inside `empty` could cross are kept, so positions
+/// reached by different words can still share an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct MemoKey {
+    empty: usize,
+    backslash: usize,
+    slash: usize,
+    spangram: Spangram,
+}
+
+/// Number of separately locked parts of the memo, so threads rarely wait on each
+/// other
+const MEMO_SHARDS: usize = 64;
+
+/// Rough size of one memo entry, including the hash table's own overhead
+const MEMO_ENTRY_BYTES: usize = 64;
+
+/// Positions proven impossible to cover, shared between threads. The same board
+/// position is reached by placing the same words in different orders, and this saves
+/// searching it again each time.
+///
+/// Each entry keeps the largest number of words the position was searched with. A
+/// position that can't be covered with that many words can't be covered with fewer.
+#[derive(Debug)]
+struct Memo {
+    shards: Vec<Mutex<HashMap<MemoKey, usize>>>,
+    /// Once a shard holds this many entries, no more are added to it
+    shard_capacity: usize,
+}
+
+impl Memo {
+    fn new(limit_bytes: usize) -> Self {
+        Memo {
+            shards: (0..MEMO_SHARDS).map(|_| Mutex::default()).collect(),
+            shard_capacity: limit_bytes / MEMO_ENTRY_BYTES / MEMO_SHARDS,
+        }
+    }
+
+    fn shard(&self, key: &MemoKey) -> &Mutex<HashMap<MemoKey, usize>> {
+        let hash = (key.empty ^ key.backslash.rotate_left(21) ^ key.slash.rotate_left(42))
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        &self.shards[(hash >> 32) % MEMO_SHARDS]
+    }
+
+    /// Whether `key` is known not to be coverable with `budget` words
+    fn known_failure(&self, key: &MemoKey, budget: usize, stats: &mut SolveStats) -> bool {
+        if self.shard_capacity == 0 {
+            return false;
+        }
+        stats.memo_lookups += 1;
+        let hit = self
+            .shard(key)
+            .lock()
+            .unwrap()
+            .get(key)
+            .is_some_and(|&failed| failed >= budget);
+        stats.memo_hits += u64::from(hit);
+        hit
+    }
+
+    /// Remember that `key` can't be covered with `budget` words
+    fn record_failure(&self, key: MemoKey, budget: usize) {
+        if self.shard_capacity == 0 {
+            return;
+        }
+        let mut shard = self.shard(&key).lock().unwrap();
+        if let Some(failed) = shard.get_mut(&key) {
+            *failed = (*failed).max(budget);
+        } else if shard.len() < self.shard_capacity {
+            shard.insert(key, budget);
+        }
+    }
+
+    /// How many positions are remembered
+    fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().len())
+            .sum()
+    }
+}
+
+/// Counters describing the work done during a solve
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolveStats {
+    /// Number of times a word was placed on the board
+    pub nodes: u64,
+    /// Number of positions abandoned because they left an empty region that could
+    /// never be filled
+    pub pruned: u64,
+    /// Number of times the empty spots split into regions that were solved separately
+    pub splits: u64,
+    /// Number of times a position was looked up in the memo of unsolvable positions
+    pub memo_lookups: u64,
+    /// Number of those lookups that found the position, saving a search
+    pub memo_hits: u64,
+    /// Number of positions remembered by the end of the solve
+    pub memo_entries: u64,
+}
+
+impl std::ops::AddAssign<&SolveStats> for SolveStats {
+    fn add_assign(&mut self, other: &SolveStats) {
+        self.nodes += other.nodes;
+        self.pruned += other.pruned;
+        self.splits += other.splits;
+        self.memo_lookups += other.memo_lookups;
+        self.memo_hits += other.memo_hits;
+        self.memo_entries += other.memo_entries;
+    }
+}
+
+/// A set of words that covers the whole board
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Solution {
+    pub words: Vec<String>,
+    /// The spots each word covers, in the order its letters are read
+    pub paths: Vec<Vec<usize>>,
+    /// Which of the words is the spangram, when solving with
+    /// [`SolveControl::spangram`]
+    pub spangram: Option<usize>,
+}
+
+/// Why [`solve`] didn't return a solution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// Every way of placing words was tried and none covers the board
+    NoSolution,
+    /// The search ran past its deadline
+    Timeout,
+    /// The search was cancelled through its [`CancelToken`]
+    Cancelled,
+    /// No word fits through this spot, so it can never be covered
+    UncoverableCell(usize),
+    /// Even using the longest words, covering the board takes more words than allowed
+    TooManyWords { min_words: usize, max_words: usize },
+    /// A word in [`SolveControl::found`] isn't one of the candidates along that path
+    NotACandidate(String),
+    /// A word in [`SolveControl::found`] overlaps or crosses one found before it
+    FoundConflict(String),
+    /// A word found without its path fits along more than one, so it isn't clear
+    /// which was found
+    AmbiguousFound {
+        word: String,
+        paths: Vec<Vec<usize>>,
+    },
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NoSolution => write!(f, "no set of words covers the board"),
+            SolveError::Timeout => write!(f, "timed out before the search finished"),
+            SolveError::Cancelled => write!(f, "cancelled before the search finished"),
+            SolveError::UncoverableCell(spot) => write!(f, "no word fits through spot {spot}"),
+            SolveError::TooManyWords {
+                min_words,
+                max_words,
+            } => write!(
+                f,
+                "covering the board takes at least {min_words} words, but at most {max_words} are allowed"
+            ),
+            SolveError::NotACandidate(word) => {
+                write!(f, "{word} isn't a candidate along the path it was found on")
+            }
+            SolveError::FoundConflict(word) => {
+                write!(f, "{word} overlaps or crosses another word that was found")
+            }
+            SolveError::AmbiguousFound { word, paths } => {
+                write!(f, "{word} fits {} ways, so its path is needed", paths.len())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// A group of words covering part of the board, as indices into the candidate list
+type Cover = SmallVec<[usize; 12]>;
+
+/// How many tasks to aim for per thread when splitting up the search. More tasks
+/// than threads lets idle threads steal work when some branches finish early.
+const TASKS_PER_THREAD: usize = 16;
+
+/// How many words deep to look for places to split the search
+const MAX_SPLIT_DEPTH: usize = 3;
+
+/// How many words a thread places between publishing its progress and checking
+/// whether it should stop
+const CHECK_INTERVAL: u64 = 1024;
+
+/// A handle for stopping a solve early from another thread. Clones share the same
+/// flag, so one can be handed to a signal handler while another goes to the solver.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A live view of a solve, which the search keeps up to date as it runs
+#[derive(Debug, Default)]
+pub struct Progress {
+    nodes: AtomicU64,
+    depth: AtomicUsize,
+    best_coverage: AtomicU32,
+    best_cover: Mutex<Vec<String>>,
+}
+
+impl Progress {
+    /// Number of words placed so far, across all threads
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    /// How many words deep one of the threads was when it last checked in
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// The most spots any set of non-conflicting words has covered so far
+    pub fn best_coverage(&self) -> u32 {
+        self.best_coverage.load(Ordering::Relaxed)
+    }
+
+    /// The words that covered [`Progress::best_coverage`] spots
+    pub fn best_cover(&self) -> Vec<String> {
+        self.best_cover.lock().unwrap().clone()
+    }
+
+    fn record_best(&self, coverage: u32, words: impl FnOnce() -> Vec<String>) {
+        let mut best_cover = self.best_cover.lock().unwrap();
+        if coverage > self.best_coverage() {
+            self.best_coverage.store(coverage, Ordering::Relaxed);
+            *best_cover = words();
+        }
+    }
+}
+
+/// Ways to watch, stop and adjust a solve while it runs
+#[derive(Debug, Clone)]
+pub struct SolveControl {
+    /// The search stops shortly after this is cancelled
+    pub cancel: CancelToken,
+    /// The search stops shortly after this time has passed
+    pub deadline: Option<Instant>,
+    pub progress: Arc<Progress>,
+    /// Roughly how many bytes to spend remembering positions that can't be solved.
+    /// Zero turns the memo off.
+    pub memo_limit: usize,
+    /// Only accept covers with exactly one spangram, a word touching two opposite
+    /// sides of the board
+    pub spangram: bool,
+    /// Words already found, and the paths they were found on. They are placed before
+    /// the search starts, so every solution includes them.
+    pub found: Vec<(String, Vec<usize>)>,
+    /// Try the best scored words first at each spot, so the first cover found is
+    /// made of good guesses.
+    pub scorer: Option<Arc<dyn Scorer>>,
+}
+
+impl Default for SolveControl {
+    fn default() -> Self {
+        SolveControl {
+            cancel: CancelToken::default(),
+            deadline: None,
+            progress: Arc::default(),
+            memo_limit: 256 * 1024 * 1024,
+            spangram: false,
+            found: vec![],
+            scorer: None,
+        }
+    }
+}
+
+impl SolveControl {
+    /// Whether the solve has been cancelled or has run past its deadline
+    pub fn should_stop(&self) -> bool {
+        self.stop_reason().is_some()
+    }
+
+    /// Why the solve should stop, if it should
+    pub fn stop_reason(&self) -> Option<SolveError> {
+        if self.cancel.is_cancelled() {
+            Some(SolveError::Cancelled)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some(SolveError::Timeout)
+        } else {
+            None
+        }
+    }
+}
+
+/// Look up a word that has already been found among the candidates, for
+/// [`SolveControl::found`]. With a path, the word must be a candidate along it.
+/// Without one, the word must fit exactly one way, and that path is used. Case is
+/// ignored.
+pub fn find_found_word(
+    words_that_fit: &[Vec<(String, Vec<usize>)>],
+    word: &str,
+    path: Option<&[usize]>,
+) -> Result<(String, Vec<usize>), SolveError> {
+    let word = word.trim().to_lowercase();
+    let paths: Vec<&Vec<usize>> = words_that_fit
+        .iter()
+        .flatten()
+        .filter(|(candidate, candidate_path)| {
+            *candidate == word && path.is_none_or(|path| path == &candidate_path[..])
+        })
+        .map(|(_, path)| path)
+        .collect();
+    match paths[..] {
+        [path] => Ok((word, path.clone())),
+        [] => Err(SolveError::NotACandidate(word)),
+        _ => Err(SolveError::AmbiguousFound {
+            paths: paths.into_iter().cloned().collect(),
+            word,
+        }),
+    }
+}
+
+/// State each thread carries through its part of the search
+struct Context<'a> {
+    stats: SolveStats,
+    control: &'a SolveControl,
+    /// Whether the work this thread is doing can no longer change the result,
+    /// because another thread found a solution earlier in the search order
+    abandoned: &'a dyn Fn() -> bool,
+    /// Set once `control` says to stop
+    stopped: bool,
+    /// The words placed by this thread so far, and the spots they cover
+    path: Cover,
+    covered: usize,
+    /// Words placed since progress was last published
+    unreported: u64,
+}
+
+impl<'a> Context<'a> {
+    fn new(control: &'a SolveControl, abandoned: &'a dyn Fn() -> bool) -> Self {
+        Context {
+            stats: SolveStats::default(),
+            control,
+            abandoned,
+            stopped: false,
+            path: smallvec![],
+            covered: 0,
+            unreported: 0,
+        }
+    }
+
+    /// Whether to give up on the current branch
+    fn should_stop(&self) -> bool {
+        self.stopped || (self.abandoned)()
+    }
+
+    /// Publish any words placed since the last report
+    fn report(&mut self) {
+        let progress = &self.control.progress;
+        progress.nodes.fetch_add(self.unreported, Ordering::Relaxed);
+        progress.depth.store(self.path.len(), Ordering::Relaxed);
+        self.unreported = 0;
+    }
+}
+
+/// A position partway into the search, to be finished by one thread
+struct Task {
+    /// The words placed to get here
+    prefix: Cover,
+    empty: usize,
+    placed: Placed,
+    budget: usize,
+}
+
+/// The candidate words in the form the search works with, computed once per solve
+struct Searcher {
+    words: Vec<String>,
+    paths: Vec<Vec<usize>>,
+    blocks: Vec<usize>,
+    /// For each spot on the board, the blocks whose lowest spot it is. The search
+    /// always fills the lowest empty spot next, so these are the only blocks that
+    /// need to be tried there.
+    by_first_spot: Vec<Vec<usize>>,
+    /// The diagonals each block covers, from [`diagonals`]. A block crosses the
+    /// placed words if it takes the other diagonal of any square they take one of.
+    diagonals: Vec<(usize, usize)>,
+    corner_mask: usize,
+    /// The blocks that touch two opposite sides of the board
+    spangrams: Vec<usize>,
+    /// Whether each block touches two opposite sides of the board
+    is_spangram: Vec<bool>,
+    /// Whether covers need exactly one spangram
+    need_spangram: bool,
+    memo: Memo,
+    min_word_len: u32,
+    max_word_len: u32,
+    board_w: usize,
+    board_h: usize,
+}
+
+impl Searcher {
+    fn new(
+        words_that_fit: &[Vec<(String, Vec<usize>)>],
+        board_w: usize,
+        board_h: usize,
+        control: &SolveControl,
+    ) -> Self {
+        // Convert all the Vec<usize> into single usizes
+        let blocks: Vec<usize> = words_that_fit
+            .iter()
+            .flat_map(|start_point| {
+                start_point
+                    .iter()
+                    .map(|(_, indices)| indices_to_bits(indices))
+            })
+            .collect();
+
+        // Get just the string out
+        let words: Vec<String> = words_that_fit
+            .iter()
+            .flat_map(|start_point| start_point.iter().map(|(word, _)| word.clone()))
+            .collect();
+        let paths: Vec<Vec<usize>> = words_that_fit
+            .iter()
+            .flat_map(|start_point| start_point.iter().map(|(_, path)| path.clone()))
+            .collect();
+
+        let mut by_first_spot = vec![vec![]; board_w * board_h];
+        for (idx, block) in blocks.iter().enumerate() {
+            by_first_spot[block.trailing_zeros() as usize].push(idx);
+        }
+        if let Some(scorer) = &control.scorer {
+            let scores: Vec<f64> = (0..words.len())
+                .map(|idx| scorer.score_placement(&words[idx], &paths[idx]))
+                .collect();
+            for candidates in &mut by_first_spot {
+                candidates.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+            }
+        }
+
+        let corner_mask = square_corner_mask(board_w, board_h);
+        let diagonals = blocks
+            .iter()
+            .map(|&block| diagonals(block, board_w, corner_mask))
+            .collect();
+
+        let is_spangram: Vec<bool> = blocks
+            .iter()
+            .map(|&block| is_spangram(block, board_w, board_h))
+            .collect();
+        let spangrams: Vec<usize> = (0..blocks.len()).filter(|&idx| is_spangram[idx]).collect();
+
+        let lengths = blocks.iter().map(|block| block.count_ones());
+        Searcher {
+            min_word_len: lengths.clone().min().unwrap_or(0),
+            max_word_len: lengths.max().unwrap_or(0),
+            words,
+            paths,
+            blocks,
+            by_first_spot,
+            diagonals,
+            corner_mask,
+            spangrams,
+            is_spangram,
+            need_spangram: control.spangram,
+            memo: Memo::new(control.memo_limit),
+            board_w,
+            board_h,
+        }
+    }
+
+    /// Start a thread's context at the end of a task's prefix
+    fn start<'a>(
+        &self,
+        task: &Task,
+        control: &'a SolveControl,
+        abandoned: &'a dyn Fn() -> bool,
+    ) -> Context<'a> {
+        let mut ctx = Context::new(control, abandoned);
+        ctx.stopped = control.should_stop();
+        for &idx in &task.prefix {
+            ctx.path.push(idx);
+            ctx.covered |= self.blocks[idx];
+        }
+        control
+            .progress
+            .record_best(ctx.covered.count_ones(), || self.words_of(&ctx.path));
+        ctx
+    }
+
+    /// Place block `idx` at the end of the thread's path, keeping progress up to date
+    fn push(&self, ctx: &mut Context, idx: usize) {
+        ctx.stats.nodes += 1;
+        ctx.unreported += 1;
+        ctx.path.push(idx);
+        ctx.covered |= self.blocks[idx];
+
+        let coverage = ctx.covered.count_ones();
+        let progress = &ctx.control.progress;
+        if coverage > progress.best_coverage() {
+            progress.record_best(coverage, || self.words_of(&ctx.path));
+        }
+
+        if ctx.unreported >= CHECK_INTERVAL {
+            ctx.report();
+            ctx.stopped = ctx.control.should_stop();
+        }
+    }
+
+    /// Take block `idx` back off the end of the thread's path
+    fn pop(&self, ctx: &mut Context, idx: usize) {
+        ctx.path.pop();
+        ctx.covered &= !self.blocks[idx];
+    }
+
+    /// Get the words for a cover, in the order they were placed
+    fn words_of(&self, cover: &[usize]) -> Vec<String> {
+        cover.iter().map(|&idx| self.words[idx].clone()).collect()
+    }
+
+    fn solution_of(&self, cover: &[usize]) -> Solution {
+        Solution {
+            words: self.words_of(cover),
+            paths: cover.iter().map(|&idx| self.paths[idx].clone()).collect(),
+            spangram: cover
+                .iter()
+                .position(|&idx| self.need_spangram && self.is_spangram[idx]),
+        }
+    }
+
+    /// Catch boards that obviously can't be covered before searching them
+    fn check_coverable(&self, max_len: usize) -> Result<(), SolveError> {
+        let full = full_board_mask(self.board_w, self.board_h);
+        let reachable = self.blocks.iter().fold(0, |acc, block| acc | block);
+        let uncovered = full & !reachable;
+        if uncovered != 0 {
+            return Err(SolveError::UncoverableCell(
+                uncovered.trailing_zeros() as usize
+            ));
+        }
+        let min_words = self.min_words(full);
+        if min_words > max_len {
+            return Err(SolveError::TooManyWords {
+                min_words,
+                max_words: max_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// The task at the top of the search, with the `found` words already placed.
+    /// Fails if any of them can't be placed.
+    fn root(&self, found: &[(String, Vec<usize>)], max_len: usize) -> Result<Task, SolveError> {
+        let budget = max_len
+            .checked_sub(found.len())
+            .ok_or(SolveError::TooManyWords {
+                min_words: found.len(),
+                max_words: max_len,
+            })?;
+        let mut root = Task {
+            prefix: smallvec![],
+            empty: full_board_mask(self.board_w, self.board_h),
+            placed: self.nothing_placed(),
+            budget,
+        };
+        for (word, path) in found {
+            let idx = (0..self.words.len())
+                .find(|&idx| self.words[idx] == *word && self.paths[idx] == *path)
+                .ok_or_else(|| SolveError::NotACandidate(word.clone()))?;
+            if !self.fits(idx, root.empty, &root.placed) {
+                return Err(SolveError::FoundConflict(word.clone()));
+            }
+            root.prefix.push(idx);
+            root.empty &= !self.blocks[idx];
+            root.placed = self.place(&root.placed, idx);
+        }
+        Ok(root)
+    }
+
+    /// The position before any words are placed
+    fn nothing_placed(&self) -> Placed {
+        Placed {
+            backslash: 0,
+            slash: 0,
+            spangram: if self.need_spangram {
+                Spangram::Needed
+            } else {
+                Spangram::Ignored
+            },
+        }
+    }
+
+    /// Everything placed so far, plus block `idx`
+    fn place(&self, placed: &Placed, idx: usize) -> Placed {
+        let (backslash, slash) = self.diagonals[idx];
+        Placed {
+            backslash: placed.backslash | backslash,
+            slash: placed.slash | slash,
+            spangram: match placed.spangram {
+                Spangram::Needed if self.is_spangram[idx] => Spangram::Used,
+                spangram => spangram,
+            },
+        }
+    }
+
+    /// The memo key for covering `empty`. A word inside `empty` can only cross a
+    /// placed word in a square where it could take the other diagonal.
+    fn memo_key(&self, empty: usize, placed: &Placed) -> MemoKey {
+        let (backslash, slash) = diagonals(empty, self.board_w, self.corner_mask);
+        MemoKey {
+            empty,
+            backslash: placed.backslash & slash,
+            slash: placed.slash & backslash,
+            spangram: placed.spangram,
+        }
+    }
+
+    /// Whether block `idx` could be placed inside `empty` without overlapping or
+    /// crossing anything, or breaking the spangram rule
+    fn fits(&self, idx: usize, empty: usize, placed: &Placed) -> bool {
+        let (backslash, slash) = self.diagonals[idx];
+        let crosses = (backslash & placed.slash) | (slash & placed.backslash) != 0;
+        let spangram_used = placed.spangram == Spangram::Used && self.is_spangram[idx];
+        !bit_overlaps(self.blocks[idx], !empty) && !crosses && !spangram_used
+    }
+
+    /// The blocks that fit inside `empty`, cover its lowest spot, and don't cross
+    /// any placed block
+    fn moves<'a>(&'a self, empty: usize, placed: &'a Placed) -> impl Iterator<Item = usize> + 'a {
+        self.by_first_spot[empty.trailing_zeros() as usize]
+            .iter()
+            .copied()
+            .filter(move |&idx| self.fits(idx, empty, placed))
+    }
+
+    /// Whether a spangram could still go somewhere in `region`
+    fn spangram_fits(&self, region: usize, placed: &Placed) -> bool {
+        self.spangrams
+            .iter()
+            .any(|&idx| self.fits(idx, region, placed))
+    }
+
+    /// Whether a cover ending at `placed` has the spangram it needs
+    fn spangram_done(placed: &Placed) -> bool {
+        placed.spangram != Spangram::Needed
+    }
+
+    /// The spangram rule to give each region when solving them separately. A needed
+    /// spangram has to go in exactly one of them, so there is a choice of rules for
+    /// every region it might fit in.
+    fn region_rules(
+        &self,
+        components: &[usize],
+        placed: &Placed,
+    ) -> SmallVec<[SmallVec<[Spangram; 4]>; 4]> {
+        if placed.spangram != Spangram::Needed {
+            return smallvec![smallvec![placed.spangram; components.len()]];
+        }
+        (0..components.len())
+            .filter(|&i| self.spangram_fits(components[i], placed))
+            .map(|spangram_region| {
+                (0..components.len())
+                    .map(|i| {
+                        if i == spangram_region {
+                            Spangram::Needed
+                        } else {
+                            Spangram::Used
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// A region is hopeless if it is smaller than the shortest word, or if no word
+    /// fits inside it starting from its lowest spot
+    fn region_fillable(&self, component: usize, placed: &Placed) -> bool {
+        component.count_ones() >= self.min_word_len
+            && self.moves(component, placed).next().is_some()
+    }
+
+    /// The fewest words that could possibly cover a region
+    fn min_words(&self, component: usize) -> usize {
+        component.count_ones().div_ceil(self.max_word_len.max(1)) as usize
+    }
+
+    /// Split `empty` into its connected regions, or `None` if any of them can never
+    /// be filled, or there is nowhere left for a needed spangram
+    fn components(
+        &self,
+        empty: usize,
+        placed: &Placed,
+        stats: &mut SolveStats,
+    ) -> Option<SmallVec<[usize; 4]>> {
+        let components = empty_components(empty, self.board_w, self.board_h);
+        if components
+            .iter()
+            .all(|&component| self.region_fillable(component, placed))
+            && (placed.spangram != Spangram::Needed
+                || components
+                    .iter()
+                    .any(|&component| self.spangram_fits(component, placed)))
+        {
+            Some(components)
+        } else {
+            stats.pruned += 1;
+            None
+        }
+    }
+
+    /// For each region, how many words may be spent on it while still leaving the
+    /// fewest possible for all the others. `None` if that is over budget.
+    fn region_budgets(&self, components: &[usize], budget: usize) -> Option<SmallVec<[usize; 4]>> {
+        let needed: SmallVec<[usize; 4]> = components.iter().map(|&c| self.min_words(c)).collect();
+        let spare = budget.checked_sub(needed.iter().sum())?;
+        Some(needed.iter().map(|n| n + spare).collect())
+    }
+
+    /// Find the first set of at most `budget` blocks that exactly covers `empty`
+    fn inner_solve(
+        &self,
+        empty: usize,
+        placed: &Placed,
+        budget: usize,
+        ctx: &mut Context,
+    ) -> Option<Cover> {
+        let key = self.memo_key(empty, placed);
+        if empty != 0 && self.memo.known_failure(&key, budget, &mut ctx.stats) {
+            return None;
+        }
+        let result = self.search(empty, placed, budget, ctx);
+        // A search cut short proves nothing
+        if result.is_none() && !ctx.should_stop() {
+            self.memo.record_failure(key, budget);
+        }
+        result
+    }
+
+    fn search(
+        &self,
+        empty: usize,
+        placed: &Placed,
+        budget: usize,
+        ctx: &mut Context,
+    ) -> Option<Cover> {
+        if empty == 0 {
+            return Self::spangram_done(placed).then(|| smallvec![]);
+        }
+        if budget == 0 || ctx.should_stop() {
+            return None;
+        }
+
+        let components = self.components(empty, placed, &mut ctx.stats)?;
+        if components.len() > 1 {
+            ctx.stats.splits += 1;
+            return self
+                .region_rules(&components, placed)
+                .iter()
+                .find_map(|rules| self.solve_regions(&components, rules, placed, budget, ctx));
+        }
+
+        let moves: Cover = self.moves(empty, placed).collect();
+        for idx in moves {
+            self.push(ctx, idx);
+            let rest = self.inner_solve(
+                empty & !self.blocks[idx],
+                &self.place(placed, idx),
+                budget - 1,
+                ctx,
+            );
+            self.pop(ctx, idx);
+
+            if let Some(mut rest) = rest {
+                rest.insert(0, idx);
+                return Some(rest);
+            }
+        }
+        None
+    }
+
+    /// Solve regions that can't affect each other one at a time, each with its own
+    /// spangram rule. Each region is covered with as few words as it can be, so the
+    /// rest have the most room.
+    fn solve_regions(
+        &self,
+        components: &[usize],
+        rules: &[Spangram],
+        placed: &Placed,
+        budget: usize,
+        ctx: &mut Context,
+    ) -> Option<Cover> {
+        let mut needed: SmallVec<[usize; 4]> =
+            components.iter().map(|&c| self.min_words(c)).collect();
+        let (depth, covered) = (ctx.path.len(), ctx.covered);
+        let mut result = Some(Cover::new());
+        for (i, &component) in components.iter().enumerate() {
+            let region_placed = placed.with_spangram(rules[i]);
+            let cover = budget.checked_sub(needed.iter().sum()).and_then(|spare| {
+                (needed[i]..=needed[i] + spare).find_map(|region_budget| {
+                    self.inner_solve(component, &region_placed, region_budget, ctx)
+                })
+            });
+            let (Some(cover), Some(result)) = (cover, result.as_mut()) else {
+                result = None;
+                break;
+            };
+
+            // Keep the solved regions on the path while the others are searched, so
+            // progress reports see everything covered so far
+            needed[i] = cover.len();
+            for &idx in &cover {
+                ctx.path.push(idx);
+                ctx.covered |= self.blocks[idx];
+            }
+            result.extend(cover);
+        }
+        ctx.path.truncate(depth);
+        ctx.covered = covered;
+        result
+    }
+
+    /// Find every set of at most `budget` blocks that exactly covers `empty`
+    fn inner_solve_all(
+        &self,
+        empty: usize,
+        placed: &Placed,
+        budget: usize,
+        ctx: &mut Context,
+    ) -> Vec<Cover> {
+        let key = self.memo_key(empty, placed);
+        if empty != 0 && self.memo.known_failure(&key, budget, &mut ctx.stats) {
+            return vec![];
+        }
+        let result = self.search_all(empty, placed, budget, ctx);
+        if result.is_empty() && !ctx.should_stop() {
+            self.memo.record_failure(key, budget);
+        }
+        result
+    }
+
+    fn search_all(
+        &self,
+        empty: usize,
+        placed: &Placed,
+        budget: usize,
+        ctx: &mut Context,
+    ) -> Vec<Cover> {
+        if empty == 0 {
+            return if Self::spangram_done(placed) {
+                vec![smallvec![]]
+            } else {
+                vec![]
+            };
+        }
+        if budget == 0 || ctx.should_stop() {
+            return vec![];
+        }
+
+        let Some(components) = self.components(empty, placed, &mut ctx.stats) else {
+            return vec![];
+        };
+        if components.len() > 1 {
+            ctx.stats.splits += 1;
+            let Some(budgets) = self.region_budgets(&components, budget) else {
+                return vec![];
+            };
+            // Every cover of one region goes with every cover of the others, as long
+            // as together they stay within budget
+            let mut all_covers = vec![];
+            for rules in self.region_rules(&components, placed) {
+                let mut covers: Vec<Cover> = vec![smallvec![]];
+                for ((&component, &region_budget), &rule) in
+                    components.iter().zip(&budgets).zip(&rules)
+                {
+                    let region_placed = placed.with_spangram(rule);
+                    let region_covers =
+                        self.inner_solve_all(component, &region_placed, region_budget, ctx);
+                    covers = covers
+                        .iter()
+                        .flat_map(|cover| {
+                            region_covers
+                                .iter()
+                                .filter(|region_cover| cover.len() + region_cover.len() <= budget)
+                                .map(|region_cover| {
+                                    cover.iter().chain(region_cover).copied().collect()
+                                })
+                        })
+                        .collect();
+                }
+                all_covers.extend(covers);
+            }
+            return all_covers;
+        }
+
+        let mut covers = vec![];
+        let moves: Cover = self.moves(empty, placed).collect();
+        for idx in moves {
+            self.push(ctx, idx);
+            let rest = self.inner_solve_all(
+                empty & !self.blocks[idx],
+                &self.place(placed, idx),
+                budget - 1,
+                ctx,
+            );
+            self.pop(ctx, idx);
+
+            covers.extend(rest.into_iter().map(|mut cover| {
+                cover.insert(0, idx);
+                cover
+            }));
+        }
+        covers
+    }
+
+    /// Count the sets of blocks that exactly cover `empty`. The result is indexed by
+    /// how many blocks the cover uses, from zero up to `budget`.
+    fn inner_count(
+        &self,
+        empty: usize,
+        placed: &Placed,
+        budget: usize,
+        ctx: &mut Context,
+    ) -> Vec<u64> {
+        let key = self.memo_key(empty, placed);
+        if empty != 0 && self.memo.known_failure(&key, budget, &mut ctx.stats) {
+            return vec![0; budget + 1];
+        }
+        let result = self.search_count(empty, placed, budget, ctx);
+        if result.iter().all(|&count| count == 0) && !ctx.should_stop() {
+            self.memo.record_failure(key, budget);
+        }
+        result
+    }
+
+    fn search_count(
+        &self,
+        empty: usize,
+        placed: &Placed,
+        budget: usize,
+        ctx: &mut Context,
+    ) -> Vec<u64> {
+        let mut counts = vec![0; budget + 1];
+        if empty == 0 {
+            counts[0] = u64::from(Self::spangram_done(placed));
+            return counts;
+        }
+        if budget == 0 || ctx.should_stop() {
+            return counts;
+        }
+
+        let Some(components) = self.components(empty, placed, &mut ctx.stats) else {
+            return counts;
+        };
+        if components.len() > 1 {
+            ctx.stats.splits += 1;
+            let Some(budgets) = self.region_budgets(&components, budget) else {
+                return counts;
+            };
+            // Combining regions multiplies their counts, spreading the words used
+            // across them in every way that stays within budget
+            for rules in self.region_rules(&components, placed) {
+                let mut rule_counts = vec![0; budget + 1];
+                rule_counts[0] = 1;
+                for ((&component, &region_budget), &rule) in
+                    components.iter().zip(&budgets).zip(&rules)
+                {
+                    let region_placed = placed.with_spangram(rule);
+                    let region_counts =
+                        self.inner_count(component, &region_placed, region_budget, ctx);
+                    let mut combined = vec![0; budget + 1];
+                    for (used, &count) in rule_counts.iter().enumerate().filter(|(_, &c)| c != 0) {
+                        for (region_used, &region_count) in region_counts.iter().enumerate() {
+                            if let Some(total) = combined.get_mut(used + region_used) {
+                                *total += count * region_count;
+                            }
+                        }
+                    }
+                    rule_counts = combined;
+                }
+                for (total, count) in counts.iter_mut().zip(rule_counts) {
+                    *total += count;
+                }
+            }
+            return counts;
+        }
+
+        let moves: Cover = self.moves(empty, placed).collect();
+        for idx in moves {
+            self.push(ctx, idx);
+            let rest = self.inner_count(
+                empty & !self.blocks[idx],
+                &self.place(placed, idx),
+                budget - 1,
+                ctx,
+            );
+            self.pop(ctx, idx);
+
+            for (used, count) in rest.into_iter().enumerate() {
+                counts[used + 1] += count;
+            }
+        }
+        counts
+    }
+
+    /// Run `search` on every task across the thread pool, returning the results in
+    /// task order, and whether any task was stopped early by `control`. Tasks after
+    /// `first_found` are abandoned.
+    fn run_tasks<T: Send>(
+        &self,
+        tasks: &[Task],
+        control: &SolveControl,
+        first_found: &AtomicUsize,
+        stats: &mut SolveStats,
+        search: impl Fn(usize, &Task, &mut Context) -> T + Sync,
+    ) -> (Vec<T>, bool) {
+        let results: Vec<(T, SolveStats, bool)> = tasks
+            .par_iter()
+            .enumerate()
+            .map(|(task_idx, task)| {
+                let abandoned = || first_found.load(Ordering::Relaxed) < task_idx;
+                let mut ctx = self.start(task, control, &abandoned);
+                let result = search(task_idx, task, &mut ctx);
+                ctx.report();
+                (result, ctx.stats, ctx.stopped)
+            })
+            .collect();
+
+        let mut stopped = false;
+        let mut out = Vec::with_capacity(results.len());
+        for (result, task_stats, task_stopped) in results {
+            *stats += &task_stats;
+            stopped |= task_stopped;
+            out.push(result);
+        }
+        stats.memo_entries = self.memo.len() as u64;
+        (out, stopped)
+    }
+
+    /// Split the top of the search tree into tasks that can be searched
+    /// independently, one per position a few words in. They come out in the same
+    /// order a single thread would visit them, so results can be combined
+    /// deterministically.
+    fn split_tasks(&self, root: Task, control: &SolveControl, stats: &mut SolveStats) -> Vec<Task> {
+        let mut split_stats = SolveStats::default();
+        let wanted = rayon::current_num_threads() * TASKS_PER_THREAD;
+        let mut tasks = vec![root];
+        for _ in 0..MAX_SPLIT_DEPTH {
+            if tasks.len() >= wanted {
+                break;
+            }
+            tasks = tasks
+                .into_iter()
+                .flat_map(|task| self.expand(task, &mut split_stats))
+                .collect();
+        }
+        control
+            .progress
+            .nodes
+            .fetch_add(split_stats.nodes, Ordering::Relaxed);
+        *stats += &split_stats;
+        tasks
+    }
+
+    /// The tasks one word further on from `task`. Tasks that are already finished,
+    /// or where the board has split into separate regions, are left as they are.
+    fn expand(&self, task: Task, stats: &mut SolveStats) -> Vec<Task> {
+        if task.empty == 0 || task.budget == 0 {
+            return vec![task];
+        }
+        let Some(components) = self.components(task.empty, &task.placed, stats) else {
+            return vec![];
+        };
+        if components.len() > 1 {
+            return vec![task];
+        }
+
+        let children = self
+            .moves(task.empty, &task.placed)
+            .map(|idx| Task {
+                prefix: task.prefix.iter().copied().chain([idx]).collect(),
+                empty: task.empty & !self.blocks[idx],
+                placed: self.place(&task.placed, idx),
+                budget: task.budget - 1,
+            })
+            .collect::<Vec<_>>();
+        stats.nodes += children.len() as u64;
+        children
+    }
+}
+
+/// Find a set of at most `max_len` words that covers the whole board.
+///
+/// The search is spread across the current rayon thread pool. Whichever thread finds
+/// a solution first, the one returned is always the first in search order. Fails
+/// with [`SolveError::Timeout`] or [`SolveError::Cancelled`] if `control` stopped the
+/// search before a solution was found.
+pub fn solve(
+    words_that_fit: &[Vec<(String, Vec<usize>)>],
+    max_len: usize,
+    board_w: usize,
+    board_h: usize,
+    control: &SolveControl,
+    stats: &mut SolveStats,
+) -> Result<Solution, SolveError> {
+    let searcher = Searcher::new(words_that_fit, board_w, board_h, control);
+    searcher.check_coverable(max_len)?;
+    let root = searcher.root(&control.found, max_len)?;
+    let tasks = searcher.split_tasks(root, control, stats);
+
+    // The earliest task known to have a solution. Any task after it is abandoned.
+    let first_found = AtomicUsize::new(usize::MAX);
+    let (covers, stopped) = searcher.run_tasks(
+        &tasks,
+        control,
+        &first_found,
+        stats,
+        |task_idx, task, ctx| {
+            let cover: Option<Cover> = searcher
+                .inner_solve(task.empty, &task.placed, task.budget, ctx)
+                .map(|rest| task.prefix.iter().chain(&rest).copied().collect());
+            if cover.is_some() {
+                first_found.fetch_min(task_idx, Ordering::Relaxed);
+            }
+            cover
+        },
+    );
+
+    match covers.into_iter().flatten().next() {
+        Some(cover) => Ok(searcher.solution_of(&cover)),
+        None if stopped => Err(control.stop_reason().unwrap_or(SolveError::Cancelled)),
+        None => Err(SolveError::NoSolution),
+    }
+}
+
+/// Find every set of at most `max_len` words that covers the whole board. If
+/// `control` stops the search early, only the solutions found so far are returned.
+/// There are none if the `found` words can't all be placed.
+pub fn solve_all(
+    words_that_fit: &[Vec<(String, Vec<usize>)>],
+    max_len: usize,
+    board_w: usize,
+    board_h: usize,
+    control: &SolveControl,
+    stats: &mut SolveStats,
+) -> Vec<Solution> {
+    let searcher = Searcher::new(words_that_fit, board_w, board_h, control);
+    let Ok(root) = searcher.root(&control.found, max_len) else {
+        return vec![];
+    };
+    let tasks = searcher.split_tasks(root, control, stats);
+
+    let first_found = AtomicUsize::new(usize::MAX);
+    let (covers, _) = searcher.run_tasks(&tasks, control, &first_found, stats, |_, task, ctx| {
+        searcher
+            .inner_solve_all(task.empty, &task.placed, task.budget, ctx)
+            .into_iter()
+            .map(|rest| task.prefix.iter().chain(&rest).copied().collect())
+            .collect::<Vec<Cover>>()
+    });
+
+    covers
+        .iter()
+        .flatten()
+        .map(|cover| searcher.solution_of(cover))
+        .collect()
+}
+
+/// Like [`solve_all`], but hands each solution to `on_solution` as soon as the part
+/// of the search that found it finishes, instead of collecting them, so they can be
+/// printed while the search goes on. They come in no particular order, and
+/// `on_solution` may be called from several threads at once. Returns how many
+/// solutions there were.
+pub fn solve_each(
+    words_that_fit: &[Vec<(String, Vec<usize>)>],
+    max_len: usize,
+    board_w: usize,
+    board_h: usize,
+    control: &SolveControl,
+    stats: &mut SolveStats,
+    on_solution: impl Fn(Solution) + Sync,
+) -> u64 {
+    let searcher = Searcher::new(words_that_fit, board_w, board_h, control);
+    let Ok(root) = searcher.root(&control.found, max_len) else {
+        return 0;
+    };
+    let tasks = searcher.split_tasks(root, control, stats);
+
+    let first_found = AtomicUsize::new(usize::MAX);
+    let (counts, _) = searcher.run_tasks(&tasks, control, &first_found, stats, |_, task, ctx| {
+        let covers = searcher.inner_solve_all(task.empty, &task.placed, task.budget, ctx);
+        for rest in &covers {
+            let cover: Cover = task.prefix.iter().chain(rest).copied().collect();
+            on_solution(searcher.solution_of(&cover));
+        }
+        covers.len() as u64
+    });
+    counts.iter().sum()
+}
+
+/// Count the sets of at most `max_len` words that cover the whole board. If
+/// `control` stops the search early, only the solutions found so far are counted.
+/// There are none if the `found` words can't all be placed.
+pub fn count_solutions(
+    words_that_fit: &[Vec<(String, Vec<usize>)>],
+    max_len: usize,
+    board_w: usize,
+    board_h: usize,
+    control: &SolveControl,
+    stats: &mut SolveStats,
+) -> u64 {
+    let searcher = Searcher::new(words_that_fit, board_w, board_h, control);
+    let Ok(root) = searcher.root(&control.found, max_len) else {
+        return 0;
+    };
+    let tasks = searcher.split_tasks(root, control, stats);
+
+    let first_found = AtomicUsize::new(usize::MAX);
+    let (counts, _) = searcher.run_tasks(&tasks, control, &first_found, stats, |_, task, ctx| {
+        searcher
+            .inner_count(task.empty, &task.placed, task.budget, ctx)
+            .iter()
+            .sum::<u64>()
+    });
+    counts.iter().sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::scoring::CategoryScorer;
+    use crate::Category;
+    use rstest::rstest;
+    use std::fs;
+
+    /// The words on the board "tal rgo esn"
+    fn talon_words_that_fit() -> Vec<Vec<(String, Vec<usize>)>> {
+        vec![
+            vec![("talon".to_string(), vec![0, 1, 2, 5, 8])],
+            vec![("argon".to_string(), vec![1, 3, 4, 5, 8])],
+            vec![
+                ("long".to_string(), vec![2, 5, 8, 4]),
+                ("lose".to_string(), vec![2, 5, 7, 6]),
+            ],
+            vec![
+                ("rage".to_string(), vec![3, 1, 4, 6]),
+                ("regs".to_string(), vec![3, 6, 4, 7]),
+            ],
+            vec![("glare".to_string(), vec![4, 2, 1, 3, 6])],
+            vec![("ogre".to_string(), vec![5, 4, 3, 6])],
+            vec![("ergo".to_string(), vec![6, 3, 4, 5])],
+            vec![("solar".to_string(), vec![7, 5, 2, 1, 3])],
+            vec![("nose".to_string(), vec![8, 5, 7, 6])],
+        ]
+    }
+
+    #[test]
+    fn test_solve() {
+        let words_that_fit = talon_words_that_fit();
+
+        let want: Vec<String> = vec!["talon".to_string(), "regs".to_string()];
+
+        let got = solve(
+            &words_that_fit,
+            2,
+            3,
+            3,
+            &SolveControl::default(),
+            &mut SolveStats::default(),
+        )
+        .unwrap()
+        .words;
+
+        assert_eq!(want, got);
+    }
+
+    #[rstest]
+    #[case(vec![("regs", vec![3, 6, 4, 7])], Ok(vec!["regs", "talon"]))]
+    #[case(vec![("long", vec![2, 5, 8, 4])], Err(SolveError::NoSolution))]
+    #[case(
+        vec![("talon", vec![0, 1, 2, 5, 8]), ("long", vec![2, 5, 8, 4])],
+        Err(SolveError::FoundConflict("long".to_string()))
+    )]
+    #[case(
+        vec![("talon", vec![0, 1, 2, 5, 7])],
+        Err(SolveError::NotACandidate("talon".to_string()))
+    )]
+    #[case(
+        vec![("talon", vec![0, 1, 2, 5, 8]), ("regs", vec![3, 6, 4, 7]), ("nose", vec![8, 5, 7, 6])],
+        Err(SolveError::TooManyWords { min_words: 3, max_words: 2 })
+    )]
+    fn test_solve_found(
+        #[case] found: Vec<(&str, Vec<usize>)>,
+        #[case] want: Result<Vec<&str>, SolveError>,
+    ) {
+        let control = SolveControl {
+            found: found
+                .into_iter()
+                .map(|(word, path)| (word.to_string(), path))
+                .collect(),
+            ..Default::default()
+        };
+        let got = solve(
+            &talon_words_that_fit(),
+            2,
+            3,
+            3,
+            &control,
+            &mut SolveStats::default(),
+        );
+        let want = want.map(|words| words.iter().map(|word| word.to_string()).collect());
+        assert_eq!(want, got.map(|solution| solution.words));
+    }
+
+    #[rstest]
+    #[case("Talon", None, Ok(("talon", vec![0, 1, 2, 5, 8])))]
+    #[case("lose", Some(vec![2, 5, 7, 6]), Ok(("lose", vec![2, 5, 7, 6])))]
+    #[case("lose", Some(vec![2, 5, 8, 4]), Err(SolveError::NotACandidate("lose".to_string())))]
+    #[case("zzz", None, Err(SolveError::NotACandidate("zzz".to_string())))]
+    fn test_find_found_word(
+        #[case] word: &str,
+        #[case] path: Option<Vec<usize>>,
+        #[case] want: Result<(&str, Vec<usize>), SolveError>,
+    ) {
+        let want = want.map(|(word, path)| (word.to_string(), path));
+        let got = find_found_word(&talon_words_that_fit(), word, path.as_deref());
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn test_find_found_word_ambiguous() {
+        let words_that_fit = vec![
+            vec![("ergo".to_string(), vec![6, 3, 4, 5])],
+            vec![("ergo".to_string(), vec![7, 3, 4, 5])],
+        ];
+        assert_eq!(
+            Err(SolveError::AmbiguousFound {
+                word: "ergo".to_string(),
+                paths: vec![vec![6, 3, 4, 5], vec![7, 3, 4, 5]],
+            }),
+            find_found_word(&words_that_fit, "ergo", None)
+        );
+    }
+
+    #[test]
+    fn test_solve_2() {
+        let board = Board::parse_flat_board("tim lta ecl", 3, 3);
+
+        let words =
+            fs::read_to_string("american_english_dictionary.txt").expect("Unable to read file");
+        let mut valid_words: Vec<&str> = words
+            .lines()
+            .filter(|s| !s.contains(char::is_uppercase))
+            .filter(|&w| !w.ends_with("'s"))
+            .filter(|&w| w.len() >= 4)
+            .collect();
+        valid_words.sort_unstable();
+        valid_words.dedup();
+
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = (0..(3 * 3))
+            .map(|start_point| board.find_valid_words_from_start(start_point, &valid_words))
+            .collect();
+
+        let mut want: Vec<String> = "title clam"
+            .split_ascii_whitespace()
+            .map(std::string::ToString::to_string)
+            .collect();
+        want.sort_unstable();
+
+        let mut got = solve(
+            &words_that_fit,
+            2,
+            3,
+            3,
+            &SolveControl::default(),
+            &mut SolveStats::default(),
+        )
+        .unwrap()
+        .words;
+        got.sort_unstable();
+
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn test_solve_diag() {
+        // This test covers the case where two words exist that cross on the diagonal,
+        // which is not allowed
+        // In this case, if diagonals were allowed, it would find "camp" and "dress"
+        // but they are not, so there is no solution
+        let board = Board::parse_flat_board("cdp amr sse", 3, 3);
+
+        let words =
+            fs::read_to_string("american_english_dictionary.txt").expect("Unable to read file");
+        let mut valid_words: Vec<&str> = words
+            .lines()
+            .filter(|s| !s.contains(char::is_uppercase))
+            .filter(|&w| !w.ends_with("'s"))
+            .filter(|&w| w.len() >= 4)
+            .collect();
+        valid_words.sort_unstable();
+        valid_words.dedup();
+
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = (0..(3 * 3))
+            .map(|start_point| board.find_valid_words_from_start(start_point, &valid_words))
+            .collect();
+
+        let got = solve(
+            &words_that_fit,
+            2,
+            3,
+            3,
+            &SolveControl::default(),
+            &mut SolveStats::default(),
+        );
+        assert_eq!(Err(SolveError::NoSolution), got);
+    }
+
+    #[rstest]
+    // Nothing reaches the middle spot
+    #[case(&[0b000000111, 0b000101000, 0b111000000], 3, SolveError::UncoverableCell(4))]
+    // Three letter words can't cover nine spots with two words
+    #[case(&[0b000000111, 0b000111000, 0b111000000], 2, SolveError::TooManyWords { min_words: 3, max_words: 2 })]
+    fn test_solve_unsolvable_up_front(
+        #[case] blocks: &[usize],
+        #[case] max_len: usize,
+        #[case] expected: SolveError,
+    ) {
+        let searcher = searcher_from_blocks(blocks);
+        assert_eq!(Err(expected), searcher.check_coverable(max_len));
+    }
+
+    #[test]
+    fn test_solve_long() {
+        let board = Board::parse_flat_board(
+            "hgueds uovaos lsnltw etcfme eoreor tuivkm tpekoo eslawn",
+            6,
+            8,
+        );
+
+        let words =
+            fs::read_to_string("american_english_dictionary.txt").expect("Unable to read file");
+        let mut valid_words: Vec<&str> = words
+            .lines()
+            .filter(|s| !s.contains(char::is_uppercase))
+            .filter(|&w| !w.ends_with("'s"))
+            .filter(|&w| w.len() >= 4)
+            .collect();
+        valid_words.sort_unstable();
+        valid_words.dedup();
+
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = (0..(6 * 8))
+            .map(|start_point| board.find_valid_words_from_start(start_point, &valid_words))
+            .collect();
+
+        let mut want: Vec<String> = "dance floss hustle moonwalk movie pets route twerk vogue"
+            .split_ascii_whitespace()
+            .map(std::string::ToString::to_string)
+            .collect();
+        want.sort_unstable();
+
+        let mut got = solve(
+            &words_that_fit,
+            9,
+            6,
+            8,
+            &SolveControl::default(),
+            &mut SolveStats::default(),
+        )
+        .unwrap()
+        .words;
+        got.sort_unstable();
+
+        assert_eq!(want, got);
+    }
+
+    #[rstest]
+    #[case(0b0000, 0b0000, false)] // both empty
+    #[case(0b0001, 0b0010, false)] // ones in different places
+    #[case(0b0010, 0b0010, true)] // direct overlap
+    #[case(0b1100, 0b0011, false)] // ones in different places
+    #[case(0b1100, 0b0100, true)] // one overlap
+    #[case(0b1010, 0b1001, true)] // one overlap
+    #[case(0b1111, 0b0000, false)] // all of one or the other
+    #[case(0b1111, 0b1111, true)] // all ones all the way
+    fn test_bit_overlaps(
+        #[case] existing: usize,
+        #[case] new_indices: usize,
+        #[case] expected: bool,
+    ) {
+        let result = bit_overlaps(existing, new_indices);
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case(0b000000000, 3, 3, vec![])] // nothing empty
+    #[case(0b111111111, 3, 3, vec![0b111111111])] // everything empty
+    #[case(0b100010001, 3, 3, vec![0b100010001])] // connected along a diagonal
+    #[case(0b100000001, 3, 3, vec![0b000000001, 0b100000000])] // opposite corners
+    #[case(0b001100, 3, 2, vec![0b000100, 0b001000])] // wrapping rows are not neighbors
+    #[case(0b11000011, 4, 2, vec![0b11000011])] // two pairs touching diagonally
+    #[case(0b10000001, 4, 2, vec![0b00000001, 0b10000000])] // far corners
+    #[case(0b101101100, 3, 3, vec![0b100100100, 0b001001000])] // last spot wraps around
+    fn test_empty_components(
+        #[case] empty: usize,
+        #[case] board_w: usize,
+        #[case] board_h: usize,
+        #[case] want: Vec<usize>,
+    ) {
+        let got = empty_components(empty, board_w, board_h);
+        assert_eq!(want, got.to_vec());
+    }
+
+    /// Build a searcher for a 3x3 board from the spots each word covers
+    fn searcher_from_blocks(blocks: &[usize]) -> Searcher {
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, &block)| {
+                let path = (0..9).filter(|spot| block & (1 << spot) != 0).collect();
+                vec![(format!("word{i}"), path)]
+            })
+            .collect();
+        Searcher::new(&words_that_fit, 3, 3, &SolveControl::default())
+    }
+
+    #[rstest]
+    #[case(0b111000000, &[0b111000000], &[], true)] // the word fills the region
+    #[case(0b111000000, &[0b000111000], &[], false)] // the word is somewhere else
+    #[case(0b000010000, &[0b000000111, 0b000111000], &[], false)] // smaller than a word
+    #[case(0b111001110, &[0b000001110, 0b000110001], &[], true)] // nothing to cross
+    #[case(0b111001110, &[0b000001110, 0b000110001], &[1], false)] // would cross
+    fn test_region_fillable(
+        #[case] component: usize,
+        #[case] blocks: &[usize],
+        #[case] selected: &[usize],
+        #[case] expected: bool,
+    ) {
+        let searcher = searcher_from_blocks(blocks);
+        let placed = selected
+            .iter()
+            .fold(searcher.nothing_placed(), |placed, &idx| {
+                searcher.place(&placed, idx)
+            });
+        assert_eq!(expected, searcher.region_fillable(component, &placed));
+    }
+
+    #[test]
+    fn test_fits_rejects_overlaps_and_crossings() {
+        let searcher = searcher_from_blocks(&[0b000000111, 0b000111000, 0b000110001, 0b000001110]);
+        let full = full_board_mask(3, 3);
+        let rejected: Vec<Vec<usize>> = (0..4)
+            .map(|i| {
+                let placed = searcher.place(&searcher.nothing_placed(), i);
+                let empty = full & !searcher.blocks[i];
+                (0..4)
+                    .filter(|&idx| !searcher.fits(idx, empty, &placed))
+                    .collect()
+            })
+            .collect();
+        // With each word placed, the words overlapping it don't fit, and neither do
+        // the two words that cross
+        let want = vec![
+            vec![0, 2, 3],
+            vec![1, 2, 3],
+            vec![0, 1, 2, 3],
+            vec![0, 1, 2, 3],
+        ];
+        assert_eq!(want, rejected);
+    }
+
+    #[test]
+    fn test_solve_regions_separately() {
+        // A 3x3 board where the first word splits the rest of the board into two
+        // regions, each of which can be filled two ways
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = vec![
+            vec![("hook".to_string(), vec![0, 1, 4, 7])],
+            vec![("uppa".to_string(), vec![2, 5, 8])],
+            vec![("ab".to_string(), vec![3, 6])],
+            vec![("ba".to_string(), vec![6, 3])],
+            vec![("uppb".to_string(), vec![8, 5, 2])],
+        ];
+
+        let mut stats = SolveStats::default();
+        let got = solve_all(
+            &words_that_fit,
+            3,
+            3,
+            3,
+            &SolveControl::default(),
+            &mut stats,
+        )
+        .into_iter()
+        .map(|solution| solution.words)
+        .collect::<Vec<_>>();
+        let want: Vec<Vec<String>> = [
+            ["hook", "uppa", "ab"],
+            ["hook", "uppa", "ba"],
+            ["hook", "uppb", "ab"],
+            ["hook", "uppb", "ba"],
+        ]
+        .iter()
+        .map(|words| words.iter().map(|w| w.to_string()).collect())
+        .collect();
+        assert_eq!(want, got);
+        assert_eq!(1, stats.splits);
+
+        let count = count_solutions(
+            &words_that_fit,
+            3,
+            3,
+            3,
+            &SolveControl::default(),
+            &mut SolveStats::default(),
+        );
+        assert_eq!(4, count);
+
+        // With only two words allowed, nothing covers the board
+        let count = count_solutions(
+            &words_that_fit,
+            2,
+            3,
+            3,
+            &SolveControl::default(),
+            &mut SolveStats::default(),
+        );
+        assert_eq!(0, count);
+    }
+
+    /// Find every word that fits on `board`, from every start point
+    fn load_words_that_fit(board: &Board) -> Vec<Vec<(String, Vec<usize>)>> {
+        let words =
+            fs::read_to_string("american_english_dictionary.txt").expect("Unable to read file");
+        let mut valid_words: Vec<&str> = words
+            .lines()
+            .filter(|s| !s.contains(char::is_uppercase))
+            .filter(|&w| !w.ends_with("'s"))
+            .filter(|&w| w.len() >= 4)
+            .collect();
+        valid_words.sort_unstable();
+        valid_words.dedup();
+
+        (0..(board.w * board.h))
+            .map(|start_point| board.find_valid_words_from_start(start_point, &valid_words))
+            .collect()
+    }
+
+    #[test]
+    fn test_solve_same_on_any_threads() {
+        let board = Board::parse_flat_board(
+            "hgueds uovaos lsnltw etcfme eoreor tuivkm tpekoo eslawn",
+            6,
+            8,
+        );
+        let words_that_fit = load_words_that_fit(&board);
+
+        let run = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                let first = solve(
+                    &words_that_fit,
+                    9,
+                    6,
+                    8,
+                    &SolveControl::default(),
+                    &mut SolveStats::default(),
+                )
+                .unwrap()
+                .words;
+                let all = solve_all(
+                    &words_that_fit,
+                    9,
+                    6,
+                    8,
+                    &SolveControl::default(),
+                    &mut SolveStats::default(),
+                );
+                let count = count_solutions(
+                    &words_that_fit,
+                    9,
+                    6,
+                    8,
+                    &SolveControl::default(),
+                    &mut SolveStats::default(),
+                );
+                (first, all, count)
+            })
+        };
+
+        let (first, all, count) = run(1);
+        assert_eq!(all.len() as u64, count);
+        assert_eq!(first, all[0].words);
+        assert_eq!((first, all, count), run(4));
+    }
+
+    #[test]
+    fn test_solve_each() {
+        let board = Board::parse_flat_board(
+            "hgueds uovaos lsnltw etcfme eoreor tuivkm tpekoo eslawn",
+            6,
+            8,
+        );
+        let words_that_fit = load_words_that_fit(&board);
+        let mut all = solve_all(
+            &words_that_fit,
+            9,
+            6,
+            8,
+            &SolveControl::default(),
+            &mut SolveStats::default(),
+        );
+        let streamed = Mutex::new(vec![]);
+        let count = solve_each(
+            &words_that_fit,
+            9,
+            6,
+            8,
+            &SolveControl::default(),
+            &mut SolveStats::default(),
+            |solution| streamed.lock().unwrap().push(solution),
+        );
+        let mut streamed = streamed.into_inner().unwrap();
+        assert_eq!(all.len() as u64, count);
+        all.sort_by(|a, b| a.paths.cmp(&b.paths));
+        streamed.sort_by(|a, b| a.paths.cmp(&b.paths));
+        assert_eq!(all, streamed);
+    }
+
+    #[test]
+    fn test_solve_cancelled() {
+        let board = Board::parse_flat_board(
+            "hgueds uovaos lsnltw etcfme eoreor tuivkm tpekoo eslawn",
+            6,
+            8,
+        );
+        let words_that_fit = load_words_that_fit(&board);
+
+        // Cancelled before it starts, the search gives up straight away
+        let control = SolveControl::default();
+        control.cancel.cancel();
+        let got = solve(
+            &words_that_fit,
+            9,
+            6,
+            8,
+            &control,
+            &mut SolveStats::default(),
+        );
+        assert_eq!(Err(SolveError::Cancelled), got);
+
+        // The same goes for a deadline that has already passed
+        let control = SolveControl {
+            deadline: Some(Instant::now()),
+            ..Default::default()
+        };
+        let got = solve_all(
+            &words_that_fit,
+            9,
+            6,
+            8,
+            &control,
+            &mut SolveStats::default(),
+        );
+        assert!(got.is_empty());
+    }
+
+    #[test]
+    fn test_progress() {
+        let board = Board::parse_flat_board("tim lta ecl", 3, 3);
+        let words_that_fit = load_words_that_fit(&board);
+
+        let control = SolveControl::default();
+        let mut stats = SolveStats::default();
+        let got = solve(&words_that_fit, 2, 3, 3, &control, &mut stats)
+            .unwrap()
+            .words;
+
+        // Once solved, the best cover is the solution itself
+        assert_eq!(9, control.progress.best_coverage());
+        assert_eq!(got, control.progress.best_cover());
+        assert_eq!(stats.nodes, control.progress.nodes());
+    }
+
+    #[test]
+    fn test_memo() {
+        let board = Board::parse_flat_board(
+            "hgueds uovaos lsnltw etcfme eoreor tuivkm tpekoo eslawn",
+            6,
+            8,
+        );
+        let words_that_fit = load_words_that_fit(&board);
+
+        let run = |memo_limit: usize| {
+            let control = SolveControl {
+                memo_limit,
+                ..Default::default()
+            };
+            let mut stats = SolveStats::default();
+            let count = count_solutions(&words_that_fit, 9, 6, 8, &control, &mut stats);
+            (count, stats)
+        };
+
+        // Remembering dead ends saves work without changing the answer
+        let (with_memo, stats) = run(SolveControl::default().memo_limit);
+        let (without_memo, no_memo_stats) = run(0);
+        assert_eq!(without_memo, with_memo);
+        assert!(stats.memo_hits > 0);
+        assert!(stats.memo_entries > 0);
+        assert!(stats.nodes < no_memo_stats.nodes);
+        assert_eq!(0, no_memo_stats.memo_lookups);
+        assert_eq!(0, no_memo_stats.memo_entries);
+    }
+
+    #[test]
+    fn test_solve_spangram() {
+        // A 4x4 board where a ring around spot 5 splits the board in two. The outer
+        // region can be covered with one spangram, with none, or with a word that is
+        // one itself.
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = [
+            ("ring", vec![0, 1, 2, 6, 10, 9, 8, 4]),
+            ("ell", vec![3, 7, 11, 15, 14, 13, 12]),
+            ("side", vec![3, 7, 11]),
+            ("dot", vec![5]),
+            ("bb", vec![13, 12]),
+            ("base", vec![15, 14, 13, 12]),
+            ("aa", vec![15, 14]),
+        ]
+        .into_iter()
+        .map(|(word, path)| vec![(word.to_string(), path)])
+        .collect();
+        let words = |solutions: Vec<Solution>| -> Vec<Vec<String>> {
+            let mut words: Vec<Vec<String>> = solutions.into_iter().map(|s| s.words).collect();
+            words.sort_unstable();
+            words
+        };
+
+        let ignored = SolveControl::default();
+        let all = solve_all(
+            &words_that_fit,
+            5,
+            4,
+            4,
+            &ignored,
+            &mut SolveStats::default(),
+        );
+        assert_eq!(3, all.len());
+        assert!(all.iter().all(|solution| solution.spangram.is_none()));
+
+        let control = SolveControl {
+            spangram: true,
+            ..Default::default()
+        };
+        let mut stats = SolveStats::default();
+        let all = solve_all(&words_that_fit, 5, 4, 4, &control, &mut stats);
+        assert_eq!(1, stats.splits);
+        let spangrams: Vec<&str> = all
+            .iter()
+            .map(|solution| solution.words[solution.spangram.unwrap()].as_str())
+            .collect();
+        assert_eq!(vec!["ell", "base"], spangrams);
+        assert_eq!(
+            vec![
+                vec!["ring", "ell", "dot"],
+                vec!["ring", "side", "base", "dot"],
+            ],
+            words(all)
+        );
+
+        let count = count_solutions(
+            &words_that_fit,
+            5,
+            4,
+            4,
+            &control,
+            &mut SolveStats::default(),
+        );
+        assert_eq!(2, count);
+
+        let first = solve(
+            &words_that_fit,
+            5,
+            4,
+            4,
+            &control,
+            &mut SolveStats::default(),
+        )
+        .unwrap();
+        assert_eq!(vec!["ring", "ell", "dot"], first.words);
+        assert_eq!(Some(1), first.spangram);
+    }
+
+    #[test]
+    fn test_solve_scored() {
+        // A board one row high, covered by one long word or two short ones
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = vec![
+            vec![
+                ("abcd".to_string(), vec![0, 1, 2, 3]),
+                ("ab".to_string(), vec![0, 1]),
+            ],
+            vec![],
+            vec![("cd".to_string(), vec![2, 3])],
+            vec![],
+        ];
+        let first = |control: &SolveControl| {
+            solve(
+                &words_that_fit,
+                2,
+                4,
+                1,
+                control,
+                &mut SolveStats::default(),
+            )
+            .unwrap()
+            .words
+        };
+        assert_eq!(vec!["abcd"], first(&SolveControl::default()));
+
+        // The best scored words are tried first at each spot
+        let category = Category::parse("short", "ab\ncd\n");
+        let control = SolveControl {
+            scorer: Some(Arc::new(CategoryScorer(category))),
+            ..Default::default()
+        };
+        assert_eq!(vec!["ab", "cd"], first(&control));
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+
+use rayon::prelude::*;
+
+use crate::{is_spangram, Board};
+
+/// Where a spangram candidate's spelling came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpangramKind {
+    /// A single dictionary word
+    Word,
+    /// A phrase from the dictionary or a phrase list, like "dance moves"
+    Phrase,
+    /// Dictionary words that happen to run together along the path, found by
+    /// [`find_compound_spangrams`]
+    Compound,
+}
+
+/// A placement that could be the puzzle's spangram
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpangramCandidate {
+    pub word: String,
+    pub path: Vec<usize>,
+    /// How often the word is used, or zero if it isn't in the frequency list. For a
+    /// compound, this is how often its rarest part is used.
+    pub frequency: u64,
+    pub kind: SpangramKind,
+    /// The words a phrase or compound is made of. Empty for a single word.
+    pub parts: Vec<String>,
+}
+
+/// Pick out the placements from [`crate::Board::find_valid_words_from_start`] that
+/// touch two opposite sides of the board, ranked by [`rank_spangrams`]. `phrases`
+/// maps the squashed spelling of each known phrase to its words.
+pub fn find_spangrams(
+    words_that_fit: &[Vec<(String, Vec<usize>)>],
+    board_w: usize,
+    board_h: usize,
+    frequencies: &HashMap<String, u64>,
+    phrases: &HashMap<String, Vec<String>>,
+) -> Vec<SpangramCandidate> {
+    let mut candidates: Vec<SpangramCandidate> = words_that_fit
+        .iter()
+        .flatten()
+        .filter(|(_, path)| spans(path, board_w, board_h))
+        .map(|(word, path)| {
+            let parts = phrases.get(word).cloned().unwrap_or_default();
+            SpangramCandidate {
+                word: word.clone(),
+                path: path.clone(),
+                frequency: frequencies.get(word).copied().unwrap_or(0),
+                kind: if parts.is_empty() {
+                    SpangramKind::Word
+                } else {
+                    SpangramKind::Phrase
+                },
+                parts,
+            }
+        })
+        .collect();
+    rank_spangrams(&mut candidates);
+    candidates
+}
+
+/// Find paths that touch two opposite sides of the board and spell two to
+/// `max_parts` of `words` run together, ranked by [`rank_spangrams`]. `words` must
+/// be sorted.
+pub fn find_compound_spangrams(
+    board: &Board,
+    words: &[&str],
+    max_parts: usize,
+    frequencies: &HashMap<String, u64>,
+) -> Vec<SpangramCandidate> {
+    let mut candidates: Vec<SpangramCandidate> = (0..board.letters.len())
+        .into_par_iter()
+        .flat_map_iter(|start_point| board.find_compounds_from_start(start_point, words, max_parts))
+        .filter(|(_, path)| spans(path, board.w, board.h))
+        .map(|(parts, path)| SpangramCandidate {
+            word: parts.concat(),
+            path,
+            frequency: parts
+                .iter()
+                .map(|part| frequencies.get(part).copied().unwrap_or(0))
+                .min()
+                .unwrap_or(0),
+            kind: SpangramKind::Compound,
+            parts,
+        })
+        .collect();
+    rank_spangrams(&mut candidates);
+    candidates
+}
+
+/// Put the best guesses first: real words and phrases, longest first and then the
+/// most common, followed by compounds of as few words as possible. Long strings of
+/// obscure words are easy to find by accident, so compounds go by how common they
+/// are before their length. The same spelling over the same spots is only kept
+/// once, as its simplest kind.
+pub fn rank_spangrams(candidates: &mut Vec<SpangramCandidate>) {
+    let guesswork = |candidate: &SpangramCandidate| match candidate.kind {
+        SpangramKind::Compound => candidate.parts.len(),
+        SpangramKind::Word | SpangramKind::Phrase => 0,
+    };
+    candidates.sort_by(|a, b| {
+        let length = b.word.len().cmp(&a.word.len());
+        let frequency = b.frequency.cmp(&a.frequency);
+        guesswork(a)
+            .cmp(&guesswork(b))
+            .then(if guesswork(a) == 0 {
+                length.then(frequency)
+            } else {
+                frequency.then(length)
+            })
+            .then_with(|| a.word.cmp(&b.word))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    let mut seen = HashSet::new();
+    candidates.retain(|candidate| {
+        let spots = candidate
+            .path
+            .iter()
+            .fold(0usize, |acc, &idx| acc | (1 << idx));
+        seen.insert((candidate.word.clone(), spots))
+    });
+}
+
+/// Whether a path touches two opposite sides of the board
+fn spans(path: &[usize], board_w: usize, board_h: usize) -> bool {
+    let word = path.iter().fold(0, |acc, &idx| acc | (1 << idx));
+    is_spangram(word, board_w, board_h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_spangrams() {
+        // On a 3x3 board
+        let words_that_fit: Vec<Vec<(String, Vec<usize>)>> = vec![
+            vec![
+                ("top".to_string(), vec![0, 1, 2]),
+                ("tall".to_string(), vec![0, 3, 6, 7]),
+                ("tee".to_string(), vec![0, 4, 8]),
+            ],
+            vec![("hit".to_string(), vec![1, 4, 7])],
+            vec![("nook".to_string(), vec![3, 4, 1, 0])],
+            vec![("pit".to_string(), vec![5, 4, 3])],
+        ];
+        let frequencies = HashMap::from([("pit".to_string(), 5), ("top".to_string(), 2)]);
+        let phrases = HashMap::from([("tee".to_string(), vec!["t".to_string(), "e".to_string()])]);
+
+        let candidates = find_spangrams(&words_that_fit, 3, 3, &frequencies, &phrases);
+        let got: Vec<(&str, u64, SpangramKind)> = candidates
+            .iter()
+            .map(|candidate| (candidate.word.as_str(), candidate.frequency, candidate.kind))
+            .collect();
+        // "nook" stays on the top left, the rest reach across
+        let want = vec![
+            ("tall", 0, SpangramKind::Word),
+            ("pit", 5, SpangramKind::Word),
+            ("top", 2, SpangramKind::Word),
+            ("hit", 0, SpangramKind::Word),
+            ("tee", 0, SpangramKind::Phrase),
+        ];
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn test_find_compound_spangrams() {
+        let board = Board::parse_flat_board("dance evomx sxxxx", 5, 3);
+        let words = ["cane", "dance", "moves", "nave", "vexes"];
+        let frequencies = HashMap::from([("dance".to_string(), 9), ("moves".to_string(), 4)]);
+
+        let candidates = find_compound_spangrams(&board, &words, 3, &frequencies);
+        let got: Vec<(&str, &[String], u64)> = candidates
+            .iter()
+            .map(|candidate| {
+                (
+                    candidate.word.as_str(),
+                    &candidate.parts[..],
+                    candidate.frequency,
+                )
+            })
+            .collect();
+        let parts = ["dance".to_string(), "moves".to_string()];
+        assert_eq!(vec![("dancemoves", &parts[..], 4)], got);
+        assert_eq!(vec![0, 1, 2, 3, 4, 8, 7, 6, 5, 10], candidates[0].path);
+    }
+
+    #[test]
+    fn test_rank_spangrams() {
+        let candidate = |word: &str, path: Vec<usize>, kind, parts: &[&str]| SpangramCandidate {
+            word: word.to_string(),
+            path,
+            frequency: 0,
+            kind,
+            parts: parts.iter().map(|part| part.to_string()).collect(),
+        };
+        let mut candidates = vec![
+            candidate(
+                "abcdefgh",
+                vec![0, 1, 2],
+                SpangramKind::Compound,
+                &["abc", "def", "gh"],
+            ),
+            candidate(
+                "abcdef",
+                vec![0, 1, 2],
+                SpangramKind::Compound,
+                &["abc", "def"],
+            ),
+            candidate(
+                "icecream",
+                vec![3, 4, 5],
+                SpangramKind::Compound,
+                &["ice", "cream"],
+            ),
+            candidate(
+                "icecream",
+                vec![5, 4, 3],
+                SpangramKind::Phrase,
+                &["ice", "cream"],
+            ),
+            candidate("top", vec![0, 1, 2], SpangramKind::Word, &[]),
+        ];
+        rank_spangrams(&mut candidates);
+        let got: Vec<(&str, SpangramKind)> = candidates
+            .iter()
+            .map(|candidate| (candidate.word.as_str(), candidate.kind))
+            .collect();
+        let want = vec![
+            ("icecream", SpangramKind::Phrase),
+            ("top", SpangramKind::Word),
+            ("abcdef", SpangramKind::Compound),
+            ("abcdefgh", SpangramKind::Compound),
+        ];
+        assert_eq!(want, got);
+    }
+}
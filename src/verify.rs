@@ -0,0 +1,260 @@
+use std::fmt;
+
+use crate::words::squash;
+use crate::{diagonals, is_spangram, square_corner_mask, Board};
+
+/// Something wrong with a proposed solution. Spots are given as `[row, column]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The word was given without a path
+    NoPath { word: String },
+    /// The path goes through an index past the end of the board
+    OffBoard { word: String, index: usize },
+    /// The path steps between two spots that aren't neighbors
+    NotAdjacent {
+        word: String,
+        from: [usize; 2],
+        to: [usize; 2],
+    },
+    /// The letters along the path spell something else
+    Misspelled { word: String, spelled: String },
+    /// The path goes through the same spot more than once
+    ReusedSpot { word: String, spot: [usize; 2] },
+    /// Two words go through the same spot
+    Overlap {
+        words: [String; 2],
+        spot: [usize; 2],
+    },
+    /// Two words cross each other diagonally in the 2x2 square with this top-left spot
+    Crossing {
+        words: [String; 2],
+        square: [usize; 2],
+    },
+    /// No word goes through the spot
+    Uncovered { spot: [usize; 2] },
+    /// No word touches two opposite sides of the board
+    NoSpangram,
+    /// More than one word touches two opposite sides of the board
+    TooManySpangrams { words: Vec<String> },
+}
+
+/// A spot written like "r2c1"
+fn name([row, col]: [usize; 2]) -> String {
+    format!("r{row}c{col}")
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::NoPath { word } => write!(f, "{word} has no path"),
+            Violation::OffBoard { word, index } => {
+                write!(f, "{word} runs off the board at spot {index}")
+            }
+            Violation::NotAdjacent { word, from, to } => write!(
+                f,
+                "{word} jumps from {} to {}, which aren't neighbors",
+                name(*from),
+                name(*to)
+            ),
+            Violation::Misspelled { word, spelled } => {
+                write!(f, "the path for {word} spells {spelled}")
+            }
+            Violation::ReusedSpot { word, spot } => {
+                write!(f, "{word} goes through {} more than once", name(*spot))
+            }
+            Violation::Overlap { words, spot } => {
+                write!(
+                    f,
+                    "{} and {} both go through {}",
+                    words[0],
+                    words[1],
+                    name(*spot)
+                )
+            }
+            Violation::Crossing { words, square } => write!(
+                f,
+                "{} and {} cross in the square below and right of {}",
+                words[0],
+                words[1],
+                name(*square)
+            ),
+            Violation::Uncovered { spot } => write!(f, "no word goes through {}", name(*spot)),
+            Violation::NoSpangram => write!(f, "no word touches two opposite sides"),
+            Violation::TooManySpangrams { words } => write!(
+                f,
+                "{} all touch two opposite sides, but only the spangram should",
+                words.join(", ")
+            ),
+        }
+    }
+}
+
+/// Check a proposed solution, given as each word and the indices of its path: every
+/// step goes to a neighbor, the letters spell the word, no spot is used twice, no
+/// two words cross, every spot is covered and exactly one word is a spangram. Every
+/// problem found is returned, so an empty list means the solution is valid. The
+/// board must be small enough for its spots to fit in a `usize`, as for the solver.
+pub fn verify_solution(board: &Board, placements: &[(String, Vec<usize>)]) -> Vec<Violation> {
+    let size = board.letters.len();
+    let spot = |idx: usize| [idx / board.w, idx % board.w];
+    let mut violations = vec![];
+    let mut owners: Vec<Option<&String>> = vec![None; size];
+    let mut blocks = Vec::with_capacity(placements.len());
+
+    for (word, path) in placements {
+        let mut block = 0;
+        if path.is_empty() {
+            violations.push(Violation::NoPath { word: word.clone() });
+        } else if let Some(&index) = path.iter().find(|&&idx| idx >= size) {
+            violations.push(Violation::OffBoard {
+                word: word.clone(),
+                index,
+            });
+        } else {
+            for step in path.windows(2) {
+                if !board.get_neighbors(step[0]).contains(&step[1]) {
+                    violations.push(Violation::NotAdjacent {
+                        word: word.clone(),
+                        from: spot(step[0]),
+                        to: spot(step[1]),
+                    });
+                }
+            }
+            let spelled: String = path.iter().map(|&idx| board.letters[idx]).collect();
+            if spelled != squash(&word.to_lowercase()) {
+                violations.push(Violation::Misspelled {
+                    word: word.clone(),
+                    spelled,
+                });
+            }
+        }
+
+        // Spots off the board are already reported, but the rest still count
+        for &idx in path.iter().filter(|&&idx| idx < size) {
+            if block & (1 << idx) != 0 {
+                violations.push(Violation::ReusedSpot {
+                    word: word.clone(),
+                    spot: spot(idx),
+                });
+                continue;
+            }
+            block |= 1 << idx;
+            match owners[idx] {
+                Some(owner) => violations.push(Violation::Overlap {
+                    words: [owner.clone(), word.clone()],
+                    spot: spot(idx),
+                }),
+                None => owners[idx] = Some(word),
+            }
+        }
+        blocks.push(block);
+    }
+
+    let corner_mask = square_corner_mask(board.w, board.h);
+    for i in 0..placements.len() {
+        for j in i + 1..placements.len() {
+            let (backslash_i, slash_i) = diagonals(blocks[i], board.w, corner_mask);
+            let (backslash_j, slash_j) = diagonals(blocks[j], board.w, corner_mask);
+            let crossings = (backslash_i & slash_j) | (backslash_j & slash_i);
+            for idx in (0..size).filter(|idx| crossings & (1 << idx) != 0) {
+                violations.push(Violation::Crossing {
+                    words: [placements[i].0.clone(), placements[j].0.clone()],
+                    square: spot(idx),
+                });
+            }
+        }
+    }
+
+    for (idx, owner) in owners.iter().enumerate() {
+        if owner.is_none() {
+            violations.push(Violation::Uncovered { spot: spot(idx) });
+        }
+    }
+
+    let spangrams: Vec<String> = placements
+        .iter()
+        .zip(&blocks)
+        .filter(|(_, &block)| is_spangram(block, board.w, board.h))
+        .map(|((word, _), _)| word.clone())
+        .collect();
+    match spangrams.len() {
+        0 => violations.push(Violation::NoSpangram),
+        1 => {}
+        _ => violations.push(Violation::TooManySpangrams { words: spangrams }),
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn words(who: &[&str]) -> [String; 2] {
+        [who[0].to_string(), who[1].to_string()]
+    }
+
+    // On "tal rgo esn", talon runs across the top and down the right side, and regs
+    // fills the bottom left
+    #[rstest]
+    #[case(vec![("talon", vec![0, 1, 2, 5, 8]), ("REGS", vec![3, 6, 4, 7])], vec![])]
+    #[case(
+        vec![("talon", vec![0, 1, 2, 5, 8]), ("gers", vec![3, 6, 4, 7])],
+        vec![Violation::Misspelled { word: "gers".to_string(), spelled: "regs".to_string() }]
+    )]
+    #[case(
+        vec![("ton", vec![0, 5, 8]), ("al", vec![1, 2]), ("regs", vec![3, 6, 4, 7])],
+        vec![Violation::NotAdjacent { word: "ton".to_string(), from: [0, 0], to: [1, 2] }]
+    )]
+    #[case(
+        vec![("talon", vec![0, 1, 2, 5, 8]), ("regsg", vec![3, 6, 4, 7, 4])],
+        vec![Violation::ReusedSpot { word: "regsg".to_string(), spot: [1, 1] }]
+    )]
+    #[case(
+        vec![("talon", vec![0, 1, 2, 5, 8]), ("regs", vec![3, 6, 4, 7]), ("go", vec![4, 5])],
+        vec![
+            Violation::Overlap { words: words(&["regs", "go"]), spot: [1, 1] },
+            Violation::Overlap { words: words(&["talon", "go"]), spot: [1, 2] },
+        ]
+    )]
+    #[case(
+        vec![("talon", vec![0, 1, 2, 5, 8]), ("rs", vec![3, 7]), ("ge", vec![4, 6])],
+        vec![Violation::Crossing { words: words(&["rs", "ge"]), square: [1, 0] }]
+    )]
+    #[case(
+        vec![("talon", vec![0, 1, 2, 5, 9]), ("regs", vec![3, 6, 4, 7])],
+        vec![
+            Violation::OffBoard { word: "talon".to_string(), index: 9 },
+            Violation::Uncovered { spot: [2, 2] },
+        ]
+    )]
+    #[case(
+        vec![("talon", vec![]), ("regs", vec![3, 6, 4, 7])],
+        vec![
+            Violation::NoPath { word: "talon".to_string() },
+            Violation::Uncovered { spot: [0, 0] },
+            Violation::Uncovered { spot: [0, 1] },
+            Violation::Uncovered { spot: [0, 2] },
+            Violation::Uncovered { spot: [1, 2] },
+            Violation::Uncovered { spot: [2, 2] },
+            Violation::NoSpangram,
+        ]
+    )]
+    #[case(
+        vec![("tal", vec![0, 1, 2]), ("rgo", vec![3, 4, 5]), ("esn", vec![6, 7, 8])],
+        vec![Violation::TooManySpangrams {
+            words: vec!["tal".to_string(), "rgo".to_string(), "esn".to_string()]
+        }]
+    )]
+    fn test_verify_solution(
+        #[case] placements: Vec<(&str, Vec<usize>)>,
+        #[case] want: Vec<Violation>,
+    ) {
+        let board = Board::parse_flat_board("tal rgo esn", 3, 3);
+        let placements: Vec<(String, Vec<usize>)> = placements
+            .into_iter()
+            .map(|(word, path)| (word.to_string(), path))
+            .collect();
+        assert_eq!(want, verify_solution(&board, &placements));
+    }
+}
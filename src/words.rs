@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+/// The entries of a dictionary file worth looking for on a board: all lowercase,
+/// not possessive, and at least four letters long. Sorted, without duplicates.
+pub fn dictionary_words(text: &str) -> Vec<&str> {
+    let mut words: Vec<&str> = text
+        .lines()
+        .filter(|s| !s.contains(char::is_uppercase))
+        .filter(|&w| !w.ends_with("'s"))
+        .filter(|&w| w.len() >= 4)
+        .collect();
+    words.sort_unstable();
+    words.dedup();
+    words
+}
+
+/// Whether a dictionary entry is made of several words, like "ice cream" or
+/// "merry-go-round"
+pub fn is_phrase(entry: &str) -> bool {
+    entry.contains([' ', '-'])
+}
+
+/// The letters of a phrase run together, the way they appear on the board
+pub fn squash(entry: &str) -> String {
+    entry.chars().filter(|c| !matches!(c, ' ' | '-')).collect()
+}
+
+/// The words a phrase is made of
+pub fn phrase_parts(entry: &str) -> Vec<String> {
+    entry
+        .split([' ', '-'])
+        .filter(|part| !part.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Read a phrase list, one phrase per line with its words separated by spaces or
+/// hyphens, e.g. "dance moves" or "FIRST-LOVE". Blank lines and lines starting with
+/// `#` are skipped.
+pub fn parse_phrases(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| phrase_parts(&line.to_lowercase()).join(" "))
+        .collect()
+}
+
+/// Read word frequencies, one word per line followed by how often it is used, e.g.
+/// "house 1234". Phrases are squashed to match the board, and lines without a
+/// count are skipped.
+pub fn parse_frequencies(text: &str) -> HashMap<String, u64> {
+    text.lines()
+        .filter_map(|line| {
+            let (word, count) = line.trim().rsplit_once(char::is_whitespace)?;
+            Some((squash(&word.trim().to_lowercase()), count.parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_dictionary_words() {
+        let text = "zebra\ncat\nHouse\nhouse\nhouse's\nice cream\nhouse\n";
+        assert_eq!(vec!["house", "ice cream", "zebra"], dictionary_words(text));
+    }
+
+    #[rstest]
+    #[case("house", false, "house", &["house"])]
+    #[case("ice cream", true, "icecream", &["ice", "cream"])]
+    #[case("merry-go-round", true, "merrygoround", &["merry", "go", "round"])]
+    fn test_phrases(
+        #[case] entry: &str,
+        #[case] phrase: bool,
+        #[case] squashed: &str,
+        #[case] parts: &[&str],
+    ) {
+        assert_eq!(phrase, is_phrase(entry));
+        assert_eq!(squashed, squash(entry));
+        assert_eq!(parts, phrase_parts(entry));
+    }
+
+    #[test]
+    fn test_parse_phrases() {
+        let text = "# Dance floor\nDANCE MOVES\n\n  first-love \nhustle\n";
+        assert_eq!(
+            vec!["dance moves", "first love", "hustle"],
+            parse_phrases(text)
+        );
+    }
+
+    #[test]
+    fn test_parse_frequencies() {
+        let text = "house 120\nDance Moves\t7\nmissing\nbad count\n";
+        let got = parse_frequencies(text);
+        let want = HashMap::from([("house".to_string(), 120), ("dancemoves".to_string(), 7)]);
+        assert_eq!(want, got);
+    }
+}
@@ -0,0 +1,84 @@
+use rstest::rstest;
+use std::process::{Command, Output};
+
+const LETTERS: &str = "hgueds uovaos lsnltw etcfme eoreor tuivkm tpekoo eslawn";
+
+/// Run the binary from the repo root, so it finds the default dictionary
+fn strands(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_strands"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .expect("Unable to run strands")
+}
+
+#[rstest]
+#[case(&["--found", "hustle"], 0)]
+#[case(&["--found", "zzzz"], 8)]
+#[case(&["--found", "hustle@r0c0-r0c1"], 8)]
+#[case(&["--found", "hustle", "--found", "hustle"], 8)]
+fn test_found_exit_code(#[case] found: &[&str], #[case] want: i32) {
+    let mut args = vec![
+        LETTERS,
+        "--min-words",
+        "6",
+        "--max-words",
+        "9",
+        "--timeout",
+        "30",
+    ];
+    args.extend(found);
+    let output = strands(&args);
+    assert_eq!(
+        Some(want),
+        output.status.code(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_puzzle_without_theme_words() {
+    let path = std::env::temp_dir().join(format!("strands-cli-{}.json", std::process::id()));
+    let rows: Vec<String> = LETTERS.split(' ').map(|row| format!("{row:?}")).collect();
+    let puzzle = format!(
+        r#"{{"width": 6, "height": 8, "letters": [{}]}}"#,
+        rows.join(", ")
+    );
+    std::fs::write(&path, puzzle).expect("Unable to write puzzle");
+    let path = path.to_str().unwrap();
+
+    // Without theme_words, the maximum number of words has to be given
+    let output = strands(&["--puzzle", path, "--timeout", "30"]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("theme_words"));
+
+    let output = strands(&["--puzzle", path, "--max-words", "9", "--timeout", "30"]);
+    std::fs::remove_file(path).expect("Unable to remove puzzle");
+    assert_eq!(
+        Some(0),
+        output.status.code(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Found solution!"));
+}
+
+#[test]
+fn test_exclude_without_candidates() {
+    let output = strands(&[
+        LETTERS,
+        "--max-words",
+        "9",
+        "--exclude",
+        "zzzz",
+        "--exclude-path",
+        "r0c0-r0c5",
+        "--timeout",
+        "30",
+    ]);
+    assert_eq!(Some(0), output.status.code());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("zzzz isn't a candidate, so excluding it changes nothing"));
+    assert!(stdout.contains("No candidate runs along r0c0-r0c5"));
+}